@@ -1,7 +1,70 @@
+use std::{env, path::Path, process::Command};
+
 use rustc_version::{version_meta, Channel};
 
 fn main() {
-	if version_meta().unwrap().channel == Channel::Nightly {
+	let meta = version_meta().unwrap();
+	if meta.channel == Channel::Nightly {
 		println!(r#"cargo:rustc-cfg=feature="unstable""#);
 	}
+
+	if env::var_os("CARGO_FEATURE_BUILD_INFO").is_some() {
+		println!("cargo:rerun-if-changed=build.rs");
+		let info = format!(
+			"commit {}, {}, {} profile, {}, tracy_full features: {}",
+			git_commit().unwrap_or_else(|| "unknown".into()),
+			meta.short_version_string,
+			env::var("PROFILE").unwrap_or_default(),
+			env::var("TARGET").unwrap_or_default(),
+			features(),
+		);
+		println!("cargo:rustc-env=TRACY_FULL_BUILD_INFO={}", info);
+	}
+}
+
+/// The commit of the repository the build happens in. Found from the output directory, which is usually inside the
+/// repository of the application being built.
+fn git_commit() -> Option<String> {
+	let git = |args: &[&str]| {
+		let output = Command::new("git")
+			.args(args)
+			.current_dir(env::var_os("OUT_DIR")?)
+			.output()
+			.ok()?;
+		output
+			.status
+			.success()
+			.then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+	};
+
+	let commit = git(&["rev-parse", "--short", "HEAD"])?;
+	// `--path-format` needs git 2.31. With older versions the commit is still embedded, but is not updated by new
+	// commits until something else triggers a rebuild.
+	// Refs are either loose files or in `packed-refs`, and Cargo rebuilds every time if a watched file is missing.
+	let watch = |reference: &str| {
+		if let Some(path) = git(&["rev-parse", "--path-format=absolute", "--git-path", reference]) {
+			if Path::new(&path).exists() {
+				println!("cargo:rerun-if-changed={}", path);
+			}
+		}
+	};
+	watch("HEAD");
+	watch("packed-refs");
+	if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+		watch(&branch);
+	}
+	Some(commit)
+}
+
+fn features() -> String {
+	if let Ok(features) = env::var("CARGO_CFG_FEATURE") {
+		return features.replace(',', " ");
+	}
+
+	// Older versions of Cargo only set a variable for each feature, with dashes replaced by underscores.
+	let mut features: Vec<_> = env::vars()
+		.filter_map(|(key, _)| Some(key.strip_prefix("CARGO_FEATURE_")?.to_lowercase()))
+		.collect();
+	features.sort();
+	features.join(" ")
 }
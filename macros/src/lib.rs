@@ -0,0 +1,232 @@
+//! Procedural macros for `tracy_full`. Use them through the re-exports in `tracy_full`, with its `instrument` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro_crate::{crate_name, FoundCrate};
+use quote::{format_ident, quote, quote_spanned};
+use syn::{parse::Parser, parse_macro_input, spanned::Spanned, Error, Expr, Ident, ItemFn, LitInt, LitStr, Path};
+
+/// Options passed to `#[instrument]`.
+#[derive(Default)]
+struct Options {
+	name: Option<LitStr>,
+	color: Option<Expr>,
+	callstack: Option<LitInt>,
+	level: Option<Ident>,
+	enabled: Option<Expr>,
+	args: Vec<Ident>,
+	krate: Option<Path>,
+}
+
+impl Options {
+	fn parse(attr: TokenStream) -> syn::Result<Self> {
+		let mut options = Options::default();
+		let parser = syn::meta::parser(|meta| {
+			if meta.path.is_ident("name") {
+				let name: LitStr = meta.value()?.parse()?;
+				if name.value().contains('\0') {
+					return Err(Error::new(name.span(), "`name` must not contain NUL characters"));
+				}
+				options.name = Some(name);
+			} else if meta.path.is_ident("color") {
+				options.color = Some(meta.value()?.parse()?);
+			} else if meta.path.is_ident("callstack") {
+				options.callstack = Some(meta.value()?.parse()?);
+			} else if meta.path.is_ident("level") {
+				let level: Ident = meta.value()?.parse()?;
+				if !["error", "warn", "info", "debug", "trace"].contains(&level.to_string().as_str()) {
+					return Err(Error::new(
+						level.span(),
+						"expected one of `error`, `warn`, `info`, `debug`, or `trace`",
+					));
+				}
+				options.level = Some(level);
+			} else if meta.path.is_ident("enabled") {
+				options.enabled = Some(meta.value()?.parse()?);
+			} else if meta.path.is_ident("args") {
+				meta.parse_nested_meta(|arg| {
+					let ident = arg.path.require_ident()?;
+					options.args.push(ident.clone());
+					Ok(())
+				})?;
+			} else if meta.path.is_ident("crate") {
+				options.krate = Some(meta.value()?.parse()?);
+			} else {
+				return Err(meta.error("expected `name`, `color`, `callstack`, `level`, `enabled`, `args`, or `crate`"));
+			}
+			Ok(())
+		});
+		parser.parse(attr)?;
+		Ok(options)
+	}
+}
+
+/// Path to the `tracy_full` crate, as seen from the crate being compiled.
+fn krate(options: &Options) -> TokenStream2 {
+	if let Some(path) = &options.krate {
+		return quote!(#path);
+	}
+
+	// `tracy_full` never instruments itself, so `Itself` can only be its tests and examples.
+	match crate_name("tracy_full") {
+		Ok(FoundCrate::Itself) => quote!(::tracy_full),
+		Ok(FoundCrate::Name(name)) => {
+			let name = Ident::new(&name, Span::call_site());
+			quote!(::#name)
+		},
+		Err(_) => quote!(::tracy_full),
+	}
+}
+
+/// Instrument a function with a zone spanning its entire body.
+///
+/// # Options
+/// - `name = "Name"`: the name of the zone. Defaults to the name of the function.
+/// - `color = expr`: the color of the zone.
+/// - `callstack = depth`: sample the callstack with the given depth.
+/// - `level = debug`: the level of the zone, compiling it out if it is above the maximum level.
+/// - `enabled = expr`: a runtime expression that decides if the zone is active. It can refer to the arguments of the
+///   function.
+/// - `args(a, b)`: record the `Debug` representation of the listed arguments as zone text.
+/// - `crate = path`: the path to `tracy_full`, if it cannot be found automatically.
+///
+/// `async fn`s are instead wrapped in a `FutureWrapper`, appearing as a separate fiber. This requires the `futures`
+/// feature, and only supports the `name`, `enabled`, and `crate` options.
+#[proc_macro_attribute]
+pub fn instrument(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let options = match Options::parse(attr) {
+		Ok(options) => options,
+		Err(e) => return e.into_compile_error().into(),
+	};
+	let func = parse_macro_input!(item as ItemFn);
+
+	let result = if func.sig.asyncness.is_some() {
+		instrument_async(&options, func)
+	} else {
+		Ok(instrument_sync(&options, func))
+	};
+	result.unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn instrument_sync(options: &Options, func: ItemFn) -> TokenStream2 {
+	let krate = krate(options);
+	let ItemFn {
+		attrs,
+		vis,
+		sig,
+		block,
+	} = func;
+
+	let stmts = &block.stmts;
+	// Zone locations only know the enclosing module on stable, so name the zone after the function.
+	let name = options
+		.name
+		.clone()
+		.unwrap_or_else(|| LitStr::new(&sig.ident.to_string(), sig.ident.span()));
+	let location = match &options.color {
+		None => quote!(#krate::get_location!(#name)),
+		Some(color) => quote!(#krate::get_location!(#name, #color)),
+	};
+	let enabled = options.enabled.as_ref().map(|e| quote!(#e)).unwrap_or_else(|| quote!(true));
+	let zone = format_ident!("__tracy_zone", span = Span::mixed_site());
+	let begin = match &options.callstack {
+		Some(depth) => quote! {
+			#krate::zone::zone_sample(#location, #krate::clamp_callstack_depth(#depth), #enabled)
+		},
+		None => quote! {
+			#krate::zone::zone(#location, #enabled)
+		},
+	};
+	let begin = match &options.level {
+		Some(level) => {
+			let level = Ident::new(&level.to_string().to_uppercase(), level.span());
+			quote! {
+				if #krate::zone::Level::#level.is_enabled() {
+					#begin
+				} else {
+					#krate::zone::Zone::inactive()
+				}
+			}
+		},
+		None => begin,
+	};
+	let args = (!options.args.is_empty()).then(|| {
+		let args = options.args.iter().map(|arg| {
+			let format = format!("{} = {{:?}}", arg);
+			quote_spanned! { arg.span() =>
+				#zone.text(&::std::format!(#format, #arg));
+			}
+		});
+		quote! {
+			if #zone.is_active() {
+				#(#args)*
+			}
+		}
+	});
+
+	quote! {
+		#(#attrs)*
+		#vis #sig {
+			let #zone = #begin;
+			#args
+			#(#stmts)*
+		}
+	}
+}
+
+fn instrument_async(options: &Options, func: ItemFn) -> syn::Result<TokenStream2> {
+	if let Some(color) = &options.color {
+		return Err(Error::new(color.span(), "`color` is not supported on `async fn`"));
+	}
+	if let Some(depth) = &options.callstack {
+		return Err(Error::new(depth.span(), "`callstack` is not supported on `async fn`"));
+	}
+	if let Some(level) = &options.level {
+		return Err(Error::new(level.span(), "`level` is not supported on `async fn`"));
+	}
+	if let Some(arg) = options.args.first() {
+		return Err(Error::new(arg.span(), "`args` is not supported on `async fn`"));
+	}
+
+	let krate = krate(options);
+	let ItemFn {
+		attrs,
+		vis,
+		sig,
+		block,
+	} = func;
+
+	let name_bytes = match &options.name {
+		Some(name) => {
+			let name = LitStr::new(&format!("{}\0", name.value()), name.span());
+			quote!(#name)
+		},
+		None => {
+			let ident = sig.ident.to_string();
+			quote!(::std::concat!(::std::module_path!(), "::", #ident, "\0"))
+		},
+	};
+	let name = format_ident!("__tracy_name", span = Span::mixed_site());
+	let future = format_ident!("__tracy_future", span = Span::mixed_site());
+	let body = match &options.enabled {
+		Some(enabled) => quote! {
+			if #enabled {
+				#krate::future::FutureWrapper::new(#name, #future).await
+			} else {
+				#future.await
+			}
+		},
+		None => quote! {
+			#krate::future::FutureWrapper::new(#name, #future).await
+		},
+	};
+
+	Ok(quote! {
+		#(#attrs)*
+		#vis #sig {
+			let #name = unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(#name_bytes.as_bytes()) };
+			let #future = async move #block;
+			#body
+		}
+	})
+}
@@ -24,6 +24,8 @@ macro_rules! tracked_allocator {
 }
 
 /// A wrapper around an allocator that tracy tracks as a memory pool.
+///
+/// Allocations are no longer tracked once [`crate::set_instrumentation_enabled`] first disables instrumentation.
 #[cfg(feature = "allocator_api")]
 pub struct TrackedAllocator<'a, T> {
 	inner: T,
@@ -49,7 +51,9 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 		#[cfg(feature = "enable")]
 		{
 			self.inner.allocate(layout).map(|value| unsafe {
-				sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				if crate::allocations_enabled() {
+					sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				}
 				value
 			})
 		}
@@ -62,7 +66,9 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 		#[cfg(feature = "enable")]
 		{
 			self.inner.allocate_zeroed(layout).map(|value| unsafe {
-				sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				if crate::allocations_enabled() {
+					sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				}
 				value
 			})
 		}
@@ -73,7 +79,9 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 
 	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+		}
 		self.inner.deallocate(ptr, layout);
 	}
 
@@ -82,9 +90,14 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			}
 			self.inner.grow(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				if active {
+					sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				}
 				value
 			})
 		}
@@ -98,9 +111,14 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			}
 			self.inner.grow_zeroed(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				if active {
+					sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				}
 				value
 			})
 		}
@@ -114,9 +132,14 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocator<'_, T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_named(ptr.as_ptr() as _, 0, self.name.as_ptr());
+			}
 			self.inner.shrink(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				if active {
+					sys::___tracy_emit_memory_alloc_named(value.as_ptr() as _, value.len(), 0, self.name.as_ptr());
+				}
 				value
 			})
 		}
@@ -157,13 +180,15 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 		#[cfg(feature = "enable")]
 		{
 			self.inner.allocate(layout).map(|value| unsafe {
-				sys::___tracy_emit_memory_alloc_callstack_named(
-					value.as_ptr() as _,
-					value.len(),
-					self.depth,
-					0,
-					self.name.as_ptr(),
-				);
+				if crate::allocations_enabled() {
+					sys::___tracy_emit_memory_alloc_callstack_named(
+						value.as_ptr() as _,
+						value.len(),
+						self.depth,
+						0,
+						self.name.as_ptr(),
+					);
+				}
 				value
 			})
 		}
@@ -176,13 +201,15 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 		#[cfg(feature = "enable")]
 		{
 			self.inner.allocate_zeroed(layout).map(|value| unsafe {
-				sys::___tracy_emit_memory_alloc_callstack_named(
-					value.as_ptr() as _,
-					value.len(),
-					self.depth,
-					0,
-					self.name.as_ptr(),
-				);
+				if crate::allocations_enabled() {
+					sys::___tracy_emit_memory_alloc_callstack_named(
+						value.as_ptr() as _,
+						value.len(),
+						self.depth,
+						0,
+						self.name.as_ptr(),
+					);
+				}
 				value
 			})
 		}
@@ -193,7 +220,9 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 
 	unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+		}
 		self.inner.deallocate(ptr, layout);
 	}
 
@@ -202,15 +231,20 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			}
 			self.inner.grow(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_callstack_named(
-					value.as_ptr() as _,
-					value.len(),
-					self.depth,
-					0,
-					self.name.as_ptr(),
-				);
+				if active {
+					sys::___tracy_emit_memory_alloc_callstack_named(
+						value.as_ptr() as _,
+						value.len(),
+						self.depth,
+						0,
+						self.name.as_ptr(),
+					);
+				}
 				value
 			})
 		}
@@ -224,15 +258,20 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			}
 			self.inner.grow_zeroed(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_callstack_named(
-					value.as_ptr() as _,
-					value.len(),
-					self.depth,
-					0,
-					self.name.as_ptr(),
-				);
+				if active {
+					sys::___tracy_emit_memory_alloc_callstack_named(
+						value.as_ptr() as _,
+						value.len(),
+						self.depth,
+						0,
+						self.name.as_ptr(),
+					);
+				}
 				value
 			})
 		}
@@ -246,15 +285,20 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 	) -> Result<NonNull<[u8]>, AllocError> {
 		#[cfg(feature = "enable")]
 		{
-			sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			let active = crate::allocations_enabled();
+			if active {
+				sys::___tracy_emit_memory_free_callstack_named(ptr.as_ptr() as _, self.depth, 0, self.name.as_ptr());
+			}
 			self.inner.shrink(ptr, old_layout, new_layout).map(|value| {
-				sys::___tracy_emit_memory_alloc_callstack_named(
-					value.as_ptr() as _,
-					value.len(),
-					self.depth,
-					0,
-					self.name.as_ptr(),
-				);
+				if active {
+					sys::___tracy_emit_memory_alloc_callstack_named(
+						value.as_ptr() as _,
+						value.len(),
+						self.depth,
+						0,
+						self.name.as_ptr(),
+					);
+				}
 				value
 			})
 		}
@@ -265,6 +309,8 @@ unsafe impl<T: Allocator> Allocator for TrackedAllocatorSampled<T> {
 }
 
 /// A tracked global allocator.
+///
+/// Allocations are no longer tracked once [`crate::set_instrumentation_enabled`] first disables instrumentation.
 pub struct GlobalAllocator<T = System> {
 	inner: T,
 }
@@ -289,14 +335,18 @@ unsafe impl<T: GlobalAlloc> GlobalAlloc for GlobalAllocator<T> {
 	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
 		let value = self.inner.alloc(layout);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc(value as _, layout.size(), 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_alloc(value as _, layout.size(), 0);
+		}
 		value
 	}
 
 	#[inline(always)]
 	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free(ptr as _, 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_free(ptr as _, 0);
+		}
 		self.inner.dealloc(ptr, layout);
 	}
 
@@ -304,17 +354,25 @@ unsafe impl<T: GlobalAlloc> GlobalAlloc for GlobalAllocator<T> {
 	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
 		let value = self.inner.alloc_zeroed(layout);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc(value as _, layout.size(), 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_alloc(value as _, layout.size(), 0);
+		}
 		value
 	}
 
 	#[inline(always)]
 	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free(ptr as _, 0);
+		let active = crate::allocations_enabled();
+		#[cfg(feature = "enable")]
+		if active {
+			sys::___tracy_emit_memory_free(ptr as _, 0);
+		}
 		let value = self.inner.realloc(ptr, layout, new_size);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc(value as _, new_size, 0);
+		if active {
+			sys::___tracy_emit_memory_alloc(value as _, new_size, 0);
+		}
 		value
 	}
 }
@@ -347,14 +405,18 @@ unsafe impl<T: GlobalAlloc> GlobalAlloc for GlobalAllocatorSampled<T> {
 	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
 		let value = self.inner.alloc(layout);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc_callstack(value as _, layout.size(), self.depth, 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_alloc_callstack(value as _, layout.size(), self.depth, 0);
+		}
 		value
 	}
 
 	#[inline(always)]
 	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free_callstack(ptr as _, self.depth, 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_free_callstack(ptr as _, self.depth, 0);
+		}
 		self.inner.dealloc(ptr, layout);
 	}
 
@@ -362,17 +424,25 @@ unsafe impl<T: GlobalAlloc> GlobalAlloc for GlobalAllocatorSampled<T> {
 	unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
 		let value = self.inner.alloc_zeroed(layout);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc_callstack(value as _, layout.size(), self.depth, 0);
+		if crate::allocations_enabled() {
+			sys::___tracy_emit_memory_alloc_callstack(value as _, layout.size(), self.depth, 0);
+		}
 		value
 	}
 
 	#[inline(always)]
 	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_free_callstack(ptr as _, self.depth, 0);
+		let active = crate::allocations_enabled();
+		#[cfg(feature = "enable")]
+		if active {
+			sys::___tracy_emit_memory_free_callstack(ptr as _, self.depth, 0);
+		}
 		let value = self.inner.realloc(ptr, layout, new_size);
 		#[cfg(feature = "enable")]
-		sys::___tracy_emit_memory_alloc_callstack(value as _, new_size, self.depth, 0);
+		if active {
+			sys::___tracy_emit_memory_alloc_callstack(value as _, new_size, self.depth, 0);
+		}
 		value
 	}
 }
@@ -53,11 +53,8 @@ where
 	#[inline(always)]
 	unsafe fn run_unsafe(&mut self, input: <Self::In as SystemInput>::Inner<'_>, world: UnsafeWorldCell) -> Self::Out {
 		#[cfg(feature = "enable")]
-		sys::___tracy_fiber_enter(self.name.as_ptr());
-		let out = self.inner.run_unsafe(input, world);
-		#[cfg(feature = "enable")]
-		sys::___tracy_fiber_leave();
-		out
+		let _fiber = crate::instrumentation_enabled().then(|| crate::Fiber::enter(self.name.as_ptr()));
+		self.inner.run_unsafe(input, world)
 	}
 
 	#[inline(always)]
@@ -0,0 +1,148 @@
+//! Runtime filtering of zone callsites.
+//!
+//! Every zone location created by [`get_location!`](crate::get_location) is registered as a callsite the first time a
+//! zone uses it. Callsites are enabled or disabled by a filter, read from the `TRACY_ZONES` environment variable when
+//! the first callsite is registered, and replaceable at any time with [`set_filter`].
+//!
+//! A filter is a comma-separated list of directives:
+//! - `target=on` or `target=off`: enable or disable zones named `target`, or zones in the module `target` and its
+//!   children.
+//! - `on` or `off`: the default for zones that no other directive matches.
+//!
+//! A directive that matches the name of a zone takes priority over directives that match its module, and directives
+//! for longer module paths take priority over shorter ones. Zones are enabled if nothing matches.
+//!
+//! ```text
+//! TRACY_ZONES="my_crate::render=off,physics=on"
+//! ```
+//!
+//! Zones that are created at the location of the caller (such as [`Zone::here`](crate::zone::Zone::here)) are not
+//! callsites, and are always enabled.
+
+use std::sync::{atomic::Ordering, Mutex};
+
+use crate::zone::ZoneLocation;
+
+pub(crate) const UNREGISTERED: u8 = 0;
+pub(crate) const ENABLED: u8 = 1;
+pub(crate) const DISABLED: u8 = 2;
+
+/// The environment variable the filter is read from.
+pub const FILTER_ENV: &str = "TRACY_ZONES";
+
+struct Directive {
+	target: Option<String>,
+	enabled: bool,
+}
+
+struct Registry {
+	head: Option<&'static ZoneLocation>,
+	filter: Option<Vec<Directive>>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+	head: None,
+	filter: None,
+});
+
+/// Replace the filter, and re-evaluate it for every registered callsite. Invalid directives are ignored.
+pub fn set_filter(filter: &str) {
+	#[cfg(feature = "enable")]
+	{
+		let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+		let filter = parse(filter);
+		let mut next = registry.head;
+		while let Some(loc) = next {
+			loc.state.store(evaluate(&filter, loc), Ordering::Relaxed);
+			next = unsafe { loc.next.load(Ordering::Relaxed).as_ref() };
+		}
+		registry.filter = Some(filter);
+	}
+}
+
+/// Call `f` with every registered callsite.
+pub fn for_each_callsite(mut f: impl FnMut(&'static ZoneLocation)) {
+	#[cfg(feature = "enable")]
+	{
+		let mut next = REGISTRY.lock().unwrap_or_else(|e| e.into_inner()).head;
+		while let Some(loc) = next {
+			f(loc);
+			next = unsafe { loc.next.load(Ordering::Relaxed).as_ref() };
+		}
+	}
+}
+
+/// Register a callsite, returning whether it is enabled.
+#[cold]
+#[cfg(feature = "enable")]
+pub(crate) fn register(loc: &'static ZoneLocation) -> bool {
+	let mut registry = REGISTRY.lock().unwrap_or_else(|e| e.into_inner());
+	// Another thread may have registered it while we were waiting for the lock.
+	let state = loc.state.load(Ordering::Relaxed);
+	if state != UNREGISTERED {
+		return state == ENABLED;
+	}
+
+	let filter = registry
+		.filter
+		.get_or_insert_with(|| std::env::var(FILTER_ENV).map(|f| parse(&f)).unwrap_or_default());
+	let state = evaluate(filter, loc);
+	loc.next.store(
+		registry
+			.head
+			.map_or(std::ptr::null_mut(), |head| head as *const _ as *mut _),
+		Ordering::Relaxed,
+	);
+	registry.head = Some(loc);
+	loc.state.store(state, Ordering::Relaxed);
+	state == ENABLED
+}
+
+fn parse(filter: &str) -> Vec<Directive> {
+	filter
+		.split(',')
+		.filter_map(|directive| {
+			let (target, enabled) = match directive.trim().rsplit_once('=') {
+				Some((target, enabled)) => (Some(target.trim().to_owned()), enabled.trim()),
+				None => (None, directive.trim()),
+			};
+			let enabled = match enabled {
+				"on" => true,
+				"off" => false,
+				_ => return None,
+			};
+			Some(Directive { target, enabled })
+		})
+		.collect()
+}
+
+#[cfg(feature = "enable")]
+fn evaluate(filter: &[Directive], loc: &ZoneLocation) -> u8 {
+	let name = loc.name();
+	let module_path = loc.module_path();
+
+	let mut default = None;
+	let mut module: Option<(usize, bool)> = None;
+	for directive in filter {
+		match &directive.target {
+			Some(target) if Some(target.as_str()) == name => {
+				return if directive.enabled { ENABLED } else { DISABLED };
+			},
+			Some(target) => {
+				let matches = module_path
+					.strip_prefix(target.as_str())
+					.is_some_and(|rest| rest.is_empty() || rest.starts_with("::"));
+				if matches && module.is_none_or(|(len, _)| target.len() >= len) {
+					module = Some((target.len(), directive.enabled));
+				}
+			},
+			None => default = Some(directive.enabled),
+		}
+	}
+
+	if module.map(|(_, enabled)| enabled).or(default).unwrap_or(true) {
+		ENABLED
+	} else {
+		DISABLED
+	}
+}
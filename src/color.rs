@@ -16,7 +16,7 @@ impl Color {
 
 impl Color {
 	#[inline(always)]
-	pub const fn new(r: u8, g: u8, b: u8) -> Color { Color((r as u32) << 16 | (g as u32) << 8 | (b as u32) << 0) }
+	pub const fn new(r: u8, g: u8, b: u8) -> Color { Color((r as u32) << 16 | (g as u32) << 8 | (b as u32)) }
 
 	#[inline(always)]
 	pub const fn none() -> Color { Color(0) }
@@ -25,7 +25,7 @@ impl Color {
 	pub const fn to_u32(&self) -> u32 { self.0 }
 }
 
-impl Into<u32> for Color {
+impl From<Color> for u32 {
 	#[inline(always)]
-	fn into(self) -> u32 { self.0 }
+	fn from(color: Color) -> u32 { color.0 }
 }
@@ -1,4 +1,8 @@
-use std::{ffi::CStr, marker::PhantomData};
+use std::{
+	ffi::CStr,
+	marker::PhantomData,
+	sync::atomic::{AtomicU64, Ordering},
+};
 
 /// Macro to make working with frame marks easier.
 ///
@@ -30,25 +34,43 @@ macro_rules! frame {
 	};
 }
 
+static FRAME_COUNT: AtomicU64 = AtomicU64::new(0);
+
 /// The processing of the main continuous frame has ended.
 ///
-/// A 'continuous frame' is some work that repeats continuously for the duration of the program.
+/// A 'continuous frame' is some work that repeats continuously for the duration of the program. The counts of zones
+/// created with `zone!(count, ...)` are plotted and reset. With the `build-info` feature, the first frame also sends
+/// `BUILD_INFO` as application info, even while instrumentation is switched off, unless `app_info!` already sent it.
 #[inline(always)]
 pub fn frame() {
 	#[cfg(feature = "enable")]
-	unsafe {
-		sys::___tracy_emit_frame_mark(std::ptr::null());
+	{
+		#[cfg(feature = "build-info")]
+		crate::send_build_info();
+		if crate::instrumentation_enabled() {
+			unsafe {
+				crate::zone::emit_call_counts();
+				sys::___tracy_emit_frame_mark(std::ptr::null());
+			}
+		}
+		FRAME_COUNT.fetch_add(1, Ordering::Relaxed);
 	}
 }
 
+/// The number of main continuous frames that have ended.
+#[inline(always)]
+pub fn frame_count() -> u64 { FRAME_COUNT.load(Ordering::Relaxed) }
+
 /// The processing of a secondary continuous frame has ended.
 ///
 /// A 'continuous frame' is some work that repeats continuously for the duration of the program.
 #[inline(always)]
 pub fn named_frame(name: &'static CStr) {
 	#[cfg(feature = "enable")]
-	unsafe {
-		sys::___tracy_emit_frame_mark(name.as_ptr());
+	if crate::instrumentation_enabled() {
+		unsafe {
+			sys::___tracy_emit_frame_mark(name.as_ptr());
+		}
 	}
 }
 
@@ -59,10 +81,16 @@ pub fn named_frame(name: &'static CStr) {
 pub fn discontinuous_frame(name: &'static CStr) -> DiscontinuousFrame {
 	#[cfg(feature = "enable")]
 	unsafe {
-		sys::___tracy_emit_frame_mark_start(name.as_ptr());
+		let active = crate::instrumentation_enabled();
+		if active {
+			#[cfg(feature = "validate")]
+			crate::validate::frame_start(name);
+			sys::___tracy_emit_frame_mark_start(name.as_ptr());
+		}
 		DiscontinuousFrame {
 			unsend: PhantomData,
 			name,
+			active,
 		}
 	}
 	#[cfg(not(feature = "enable"))]
@@ -79,14 +107,20 @@ pub struct DiscontinuousFrame {
 	name: &'static CStr,
 	#[cfg(not(feature = "enable"))]
 	name: (),
+	#[cfg(feature = "enable")]
+	active: bool,
 }
 
 impl Drop for DiscontinuousFrame {
 	#[inline(always)]
 	fn drop(&mut self) {
 		#[cfg(feature = "enable")]
-		unsafe {
-			sys::___tracy_emit_frame_mark_end(self.name.as_ptr());
+		if self.active {
+			unsafe {
+				#[cfg(feature = "validate")]
+				crate::validate::frame_end(self.name);
+				sys::___tracy_emit_frame_mark_end(self.name.as_ptr());
+			}
 		}
 	}
 }
@@ -120,13 +154,15 @@ pub struct Image<'a> {
 #[inline(always)]
 pub fn frame_image(image: Image) {
 	#[cfg(feature = "enable")]
-	unsafe {
-		sys::___tracy_emit_frame_image(
-			image.data.as_ptr() as *const _,
-			image.width,
-			image.height,
-			image.lag,
-			image.flip as _,
-		);
+	if crate::instrumentation_enabled() {
+		unsafe {
+			sys::___tracy_emit_frame_image(
+				image.data.as_ptr() as *const _,
+				image.width,
+				image.height,
+				image.lag,
+				image.flip as _,
+			);
+		}
 	}
 }
@@ -43,15 +43,16 @@ impl<T: Future> Future for FutureWrapper<'_, T> {
 	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
 		#[cfg(feature = "enable")]
 		unsafe {
-			sys::___tracy_fiber_enter(self.name.as_ptr());
+			if !crate::instrumentation_enabled() {
+				return self.map_unchecked_mut(|this| &mut this.inner).poll(cx);
+			}
+
+			// Returns to the fiber of the enclosing future, if there is one, when dropped.
+			let _fiber = crate::Fiber::enter(self.name.as_ptr());
 
 			let this = self.get_unchecked_mut();
 			let inner = Pin::new_unchecked(&mut this.inner);
-			let val = inner.poll(cx);
-
-			sys::___tracy_fiber_enter(this.name.as_ptr());
-			sys::___tracy_fiber_leave();
-			val
+			inner.poll(cx)
 		}
 
 		#[cfg(not(feature = "enable"))]
@@ -3,25 +3,38 @@
 #![allow(unused_imports)]
 #![allow(unused_mut)]
 #![allow(unused_variables)]
+#![allow(clippy::tabs_in_doc_comments)]
 #![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![cfg_attr(feature = "unstable", feature(const_type_name))]
-#![cfg_attr(feature = "unstable", feature(generic_const_exprs))]
 
-use std::{error::Error, ffi::CString};
+use std::{
+	cell::Cell,
+	error::Error,
+	ffi::{c_char, CString},
+	sync::atomic::{AtomicBool, AtomicU8, Ordering},
+	time::Duration,
+};
 
-#[doc(hidden)]
-pub use once_cell;
+#[cfg(feature = "instrument")]
+pub use tracy_full_macros::instrument;
 
 pub mod alloc;
 #[cfg(feature = "bevy")]
 pub mod bevy;
+pub mod callsite;
 pub mod color;
 pub mod frame;
 #[cfg(feature = "futures")]
 pub mod future;
+#[cfg(feature = "log")]
+pub mod log;
+pub mod message;
+pub mod perf;
 pub mod plot;
 #[cfg(feature = "tracing")]
 pub mod tracing;
+#[cfg(all(feature = "enable", feature = "validate"))]
+mod validate;
 #[cfg(feature = "wgpu")]
 pub mod wgpu;
 pub mod zone;
@@ -54,6 +67,122 @@ where
 	}
 }
 
+static INSTRUMENTATION: AtomicBool = AtomicBool::new(true);
+static ALLOCATIONS: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable all emission at runtime. Instrumentation is enabled by default.
+///
+/// While disabled, zones, plots, messages, frame marks, fibers, tracing spans, and allocations are not sent to Tracy.
+/// Zones and frames that began before the switch still end. Allocations stay off once instrumentation is first
+/// disabled, because Tracy cannot be told about frees of memory allocated while it was off.
+#[inline(always)]
+pub fn set_instrumentation_enabled(enabled: bool) {
+	if !enabled {
+		ALLOCATIONS.store(false, Ordering::Relaxed);
+	}
+	INSTRUMENTATION.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether instrumentation is enabled. Always false without the `enable` feature.
+#[inline(always)]
+pub fn instrumentation_enabled() -> bool { cfg!(feature = "enable") && INSTRUMENTATION.load(Ordering::Relaxed) }
+
+/// Whether allocations are sent to Tracy, which stops for good the first time instrumentation is disabled.
+#[inline(always)]
+pub(crate) fn allocations_enabled() -> bool { cfg!(feature = "enable") && ALLOCATIONS.load(Ordering::Relaxed) }
+
+/// Set information about the application, shown with the capture in Tracy. Can be called several times. Text longer
+/// than [`message::MAX_LEN`] is truncated.
+#[inline(always)]
+pub fn set_app_info(info: &str) {
+	#[cfg(feature = "enable")]
+	unsafe {
+		let info = truncate(info, message::MAX_LEN);
+		sys::___tracy_emit_message_appinfo(info.as_ptr() as _, info.len());
+	}
+}
+
+/// The commit, rustc version, profile, and target of the build, and the features `tracy_full` was built with, embedded
+/// with the `build-info` feature. The features of the application are not known to `tracy_full`.
+#[cfg(feature = "build-info")]
+pub const BUILD_INFO: &str = env!("TRACY_FULL_BUILD_INFO");
+
+#[cfg(all(feature = "enable", feature = "build-info"))]
+static BUILD_INFO_SENT: std::sync::Once = std::sync::Once::new();
+
+/// Send [`BUILD_INFO`] as application info, unless it was already sent.
+#[cfg(all(feature = "enable", feature = "build-info"))]
+pub(crate) fn send_build_info() { BUILD_INFO_SENT.call_once(|| set_app_info(&format!("Build: {}", BUILD_INFO))); }
+
+/// Send `app`, followed by [`BUILD_INFO`] with the `build-info` feature, as application info.
+#[doc(hidden)]
+#[inline(always)]
+pub fn set_crate_info(app: &str) {
+	#[cfg(all(feature = "enable", feature = "build-info"))]
+	return {
+		BUILD_INFO_SENT.call_once(|| {});
+		set_app_info(&format!("{}, {}", app, BUILD_INFO));
+	};
+
+	#[cfg(not(all(feature = "enable", feature = "build-info")))]
+	set_app_info(app);
+}
+
+/// How long the panic hook waits for data to reach a connected profiler.
+const PANIC_FLUSH_WAIT: Duration = Duration::from_millis(200);
+
+/// Install a panic hook that reports panics in the capture, before calling the previous hook.
+///
+/// The panic message, location, and zone stack are emitted as a red message with a callstack. With `panic = "abort"`,
+/// the zones of the panicking thread are also ended, and if a profiler is connected, the hook waits briefly so that the
+/// data reaches it before the process dies. When unwinding, the zones end as they are dropped, and caught panics do not
+/// wait.
+pub fn install_panic_hook() {
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		#[cfg(feature = "enable")]
+		{
+			let thread = std::thread::current();
+			let mut text = format!("thread '{}' panicked", thread.name().unwrap_or("<unnamed>"));
+			if let Some(location) = info.location() {
+				text += &format!(" at {}", location);
+			}
+			let payload = info.payload();
+			let payload = match payload.downcast_ref::<&str>() {
+				Some(payload) => payload,
+				None => payload.downcast_ref::<String>().map_or("Box<dyn Any>", String::as_str),
+			};
+			text += &format!(": {}", payload);
+			let stack = zone::current_stack();
+			if !stack.is_empty() {
+				text += &format!("\nzones: {}", stack.join(" > "));
+			}
+			message::message(&text, Some(color::Color::RED), 62);
+
+			if cfg!(panic = "abort") {
+				zone::end_stack();
+				if unsafe { sys::___tracy_connected() } != 0 {
+					std::thread::sleep(PANIC_FLUSH_WAIT);
+				}
+			}
+		}
+		previous(info);
+	}));
+}
+
+/// The longest prefix of `text` that is at most `len` bytes long and ends on a character boundary.
+#[inline(always)]
+pub(crate) fn truncate(text: &str, len: usize) -> &str {
+	if text.len() <= len {
+		return text;
+	}
+	let mut end = len;
+	while !text.is_char_boundary(end) {
+		end -= 1;
+	}
+	&text[..end]
+}
+
 /// Clamp a requested callstack depth to the maximum supported by tracy (62).
 #[inline(always)]
 pub const fn clamp_callstack_depth(depth: u32) -> u32 {
@@ -64,6 +193,88 @@ pub const fn clamp_callstack_depth(depth: u32) -> u32 {
 	}
 }
 
+#[cfg(feature = "enable")]
+static GPU_CONTEXTS: AtomicU8 = AtomicU8::new(0);
+
+/// Allocate the id of a new GPU context. Panics if all 255 contexts are in use.
+#[cfg(feature = "enable")]
+pub(crate) fn next_gpu_context() -> u8 {
+	let next = GPU_CONTEXTS.fetch_add(1, Ordering::Relaxed);
+	if next == 255 {
+		panic!("Too many contexts");
+	}
+
+	next
+}
+
+#[cfg(all(feature = "enable", feature = "futures"))]
+thread_local! {
+	static FIBER: Cell<*const c_char> = const { Cell::new(std::ptr::null()) };
+}
+
+/// The fiber the current thread is in, or null.
+#[cfg(feature = "enable")]
+#[inline(always)]
+pub(crate) fn current_fiber() -> *const c_char {
+	#[cfg(feature = "futures")]
+	return FIBER.try_with(|fiber| fiber.get()).unwrap_or(std::ptr::null());
+
+	#[cfg(not(feature = "futures"))]
+	std::ptr::null()
+}
+
+/// A fiber the current thread is in. Dropping it returns to the fiber the thread was in before, even when unwinding
+/// from a panic.
+#[cfg(all(feature = "enable", feature = "futures"))]
+pub(crate) struct Fiber {
+	previous: *const c_char,
+}
+
+#[cfg(all(feature = "enable", feature = "futures"))]
+impl Fiber {
+	/// Switch the current thread to the fiber `name`.
+	#[inline(always)]
+	pub(crate) unsafe fn enter(name: *const c_char) -> Self {
+		sys::___tracy_fiber_enter(name);
+		Self {
+			previous: FIBER.try_with(|fiber| fiber.replace(name)).unwrap_or(std::ptr::null()),
+		}
+	}
+}
+
+#[cfg(all(feature = "enable", feature = "futures"))]
+impl Drop for Fiber {
+	#[inline(always)]
+	fn drop(&mut self) {
+		unsafe { fiber_switch(self.previous) };
+		let _ = FIBER.try_with(|fiber| fiber.set(self.previous));
+	}
+}
+
+/// Send the name and version of the calling crate as application info, followed by [`BUILD_INFO`] with the
+/// `build-info` feature.
+///
+/// # Example
+/// ```
+/// tracy_full::app_info!(); // "tracy_full 1.11.0"
+/// ```
+#[macro_export]
+macro_rules! app_info {
+	() => {
+		$crate::set_crate_info(concat!(env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")))
+	};
+}
+
+/// Switch to `fiber`, or leave fibers if it is null, without changing the fiber the thread is in.
+#[cfg(all(feature = "enable", feature = "futures"))]
+pub(crate) unsafe fn fiber_switch(fiber: *const c_char) {
+	if fiber.is_null() {
+		sys::___tracy_fiber_leave();
+	} else {
+		sys::___tracy_fiber_enter(fiber);
+	}
+}
+
 /// Create a `&'static CStr` from a string literal.
 #[macro_export]
 macro_rules! c_str {
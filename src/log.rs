@@ -0,0 +1,122 @@
+//! A `log` backend that sends records to Tracy as messages.
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::color::Color;
+
+/// A logger that sends records to Tracy as messages, colored by level and prefixed with their target.
+///
+/// # Example
+/// ```
+/// # use tracy_full::log::TracyLogger;
+/// TracyLogger::new().with_error_callstack(16).init().unwrap();
+/// log::error!("Failed to load"); // A red message in Tracy, with a callstack.
+/// ```
+///
+/// To keep an existing logger, such as `env_logger`, use [`TracyLogger::tee`]:
+/// ```ignore
+/// let env = env_logger::Builder::from_default_env().build();
+/// let level = env.filter();
+/// log::set_boxed_logger(Box::new(TracyLogger::new().tee(env)))?;
+/// log::set_max_level(level);
+/// ```
+pub struct TracyLogger {
+	level: LevelFilter,
+	error_callstack: u32,
+}
+
+impl TracyLogger {
+	/// A logger that sends every record, without callstacks.
+	#[inline(always)]
+	pub const fn new() -> Self {
+		Self {
+			level: LevelFilter::Trace,
+			error_callstack: 0,
+		}
+	}
+
+	/// Only send records at or above `level`.
+	#[inline(always)]
+	pub const fn with_level(mut self, level: LevelFilter) -> Self {
+		self.level = level;
+		self
+	}
+
+	/// Capture a callstack of `depth` frames with error records.
+	#[inline(always)]
+	pub const fn with_error_callstack(mut self, depth: u32) -> Self {
+		self.error_callstack = crate::clamp_callstack_depth(depth);
+		self
+	}
+
+	/// Send records to both this logger and `other`.
+	#[inline(always)]
+	pub const fn tee<L: Log>(self, other: L) -> Tee<Self, L> { Tee::new(self, other) }
+
+	/// Install this logger as the global logger, and set the maximum level to its level.
+	pub fn init(self) -> Result<(), SetLoggerError> {
+		let level = self.level;
+		log::set_boxed_logger(Box::new(self))?;
+		log::set_max_level(level);
+		Ok(())
+	}
+}
+
+impl Default for TracyLogger {
+	#[inline(always)]
+	fn default() -> Self { Self::new() }
+}
+
+impl Log for TracyLogger {
+	#[inline(always)]
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		metadata.level() <= self.level && crate::instrumentation_enabled()
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+
+		let (color, depth) = match record.level() {
+			log::Level::Error => (Some(Color::RED), self.error_callstack),
+			log::Level::Warn => (Some(Color::YELLOW), 0),
+			log::Level::Info => (Some(Color::GREEN), 0),
+			log::Level::Debug => (Some(Color::CYAN), 0),
+			log::Level::Trace => (None, 0),
+		};
+		crate::message::message_fmt(format_args!("{}: {}", record.target(), record.args()), color, depth);
+	}
+
+	fn flush(&self) {}
+}
+
+/// A logger that sends records to two loggers.
+pub struct Tee<A, B> {
+	first: A,
+	second: B,
+}
+
+impl<A: Log, B: Log> Tee<A, B> {
+	#[inline(always)]
+	pub const fn new(first: A, second: B) -> Self { Self { first, second } }
+}
+
+impl<A: Log, B: Log> Log for Tee<A, B> {
+	#[inline(always)]
+	fn enabled(&self, metadata: &Metadata) -> bool { self.first.enabled(metadata) || self.second.enabled(metadata) }
+
+	fn log(&self, record: &Record) {
+		if self.first.enabled(record.metadata()) {
+			self.first.log(record);
+		}
+		if self.second.enabled(record.metadata()) {
+			self.second.log(record);
+		}
+	}
+
+	fn flush(&self) {
+		self.first.flush();
+		self.second.flush();
+	}
+}
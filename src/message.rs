@@ -0,0 +1,128 @@
+//! Messages in Tracy's message log.
+
+use std::{cell::RefCell, ffi::CStr, fmt, fmt::Write};
+
+use crate::{clamp_callstack_depth, color::Color};
+
+/// Emit a message to the message log, formatted like `format!`.
+///
+/// # Example
+/// ```
+/// # use tracy_full::{color::Color, message};
+/// # let path = "level.bin";
+/// message!("Loading started"); // Static text, sent without copying the string.
+/// message!("Loading {}", path);
+/// message!(Color::RED, "Failed to load {}", path);
+/// message!(callstack = 16, Color::RED, "Failed to load {}", path); // With a callstack of 16 frames.
+/// ```
+#[macro_export]
+macro_rules! message {
+	(@emit $color:expr, $depth:expr, $fmt:literal $(, $($arg:tt)*)?) => {{
+		const PLAIN: bool = $crate::message::is_plain($fmt);
+		if PLAIN {
+			$crate::message::message_literal($crate::c_str!($fmt), $color, $depth);
+		} else {
+			$crate::message::message_fmt(format_args!($fmt $(, $($arg)*)?), $color, $depth);
+		}
+	}};
+
+	(@callstack $depth:expr, $fmt:literal $(, $($arg:tt)*)?) => {
+		$crate::message!(@emit None, $depth, $fmt $(, $($arg)*)?)
+	};
+
+	(@callstack $depth:expr, $color:expr, $fmt:literal $(, $($arg:tt)*)?) => {
+		$crate::message!(@emit Some($color), $depth, $fmt $(, $($arg)*)?)
+	};
+
+	(callstack = $depth:expr, $($rest:tt)+) => {
+		$crate::message!(@callstack $depth, $($rest)+)
+	};
+
+	($fmt:literal $(, $($arg:tt)*)?) => {
+		$crate::message!(@callstack 0, $fmt $(, $($arg)*)?)
+	};
+
+	($color:expr, $fmt:literal $(, $($arg:tt)*)?) => {
+		$crate::message!(@callstack 0, $color, $fmt $(, $($arg)*)?)
+	};
+}
+
+/// The longest message Tracy accepts, in bytes. Longer messages are truncated.
+pub const MAX_LEN: usize = u16::MAX as usize - 1;
+
+/// Whether a format string is sent as is, because it has no arguments or escaped braces.
+#[doc(hidden)]
+pub const fn is_plain(fmt: &str) -> bool {
+	let bytes = fmt.as_bytes();
+	let mut i = 0;
+	while i < bytes.len() {
+		if bytes[i] == b'{' || bytes[i] == b'}' {
+			return false;
+		}
+		i += 1;
+	}
+	true
+}
+
+/// Emit a message, optionally colored, with a callstack of `depth` frames. No callstack is captured if `depth` is 0.
+/// Text longer than [`MAX_LEN`] is truncated.
+#[inline(always)]
+pub fn message(text: &str, color: Option<Color>, depth: u32) {
+	#[cfg(feature = "enable")]
+	if crate::instrumentation_enabled() {
+		let text = crate::truncate(text, MAX_LEN);
+		let depth = clamp_callstack_depth(depth) as _;
+		unsafe {
+			match color {
+				Some(color) => sys::___tracy_emit_messageC(text.as_ptr() as _, text.len(), color.to_u32(), depth),
+				None => sys::___tracy_emit_message(text.as_ptr() as _, text.len(), depth),
+			}
+		}
+	}
+}
+
+/// Emit a static message. The text is not copied, which makes this cheaper than [`message`].
+#[inline(always)]
+pub fn message_literal(text: &'static CStr, color: Option<Color>, depth: u32) {
+	#[cfg(feature = "enable")]
+	if crate::instrumentation_enabled() {
+		if text.to_bytes().len() > MAX_LEN {
+			return message(&text.to_string_lossy(), color, depth);
+		}
+		let depth = clamp_callstack_depth(depth) as _;
+		unsafe {
+			match color {
+				Some(color) => sys::___tracy_emit_messageLC(text.as_ptr(), color.to_u32(), depth),
+				None => sys::___tracy_emit_messageL(text.as_ptr(), depth),
+			}
+		}
+	}
+}
+
+/// Emit a formatted message. The arguments are only formatted if instrumentation is enabled.
+#[inline(always)]
+pub fn message_fmt(args: fmt::Arguments, color: Option<Color>, depth: u32) {
+	#[cfg(feature = "enable")]
+	{
+		thread_local! {
+			static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+		}
+
+		if !crate::instrumentation_enabled() {
+			return;
+		}
+		if let Some(text) = args.as_str() {
+			return message(text, color, depth);
+		}
+
+		BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+			Ok(mut buffer) => {
+				buffer.clear();
+				let _ = buffer.write_fmt(args);
+				message(&buffer, color, depth);
+			},
+			// Formatting the arguments emitted another message on this thread.
+			Err(_) => message(&fmt::format(args), color, depth),
+		});
+	}
+}
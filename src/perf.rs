@@ -0,0 +1,316 @@
+//! Per-zone performance counters, read with `perf_event_open` on Linux.
+//!
+//! Zones created with `zone!(perf, ...)` read the counters of the current thread when they begin and end, and attach
+//! the differences to the zone as text. Counters are opened once per thread, and counters that cannot be opened are
+//! skipped: hardware counters are usually unavailable in containers and virtual machines, while [`Counter::TaskClock`]
+//! and [`Counter::PageFaults`] only need `perf_event_paranoid` to be at most 2, the default. Context switches happen in
+//! the kernel, so [`Counter::ContextSwitches`] also counts kernel events, and needs `perf_event_paranoid` to be at most
+//! 1.
+//!
+//! Without the `perf-counters` feature, or on other platforms, no counters are read.
+
+use std::{
+	fmt,
+	ops::Deref,
+	sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::zone::{Zone, ZoneGuard};
+
+/// A performance counter.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Counter {
+	/// Retired instructions.
+	Instructions,
+	/// CPU cycles.
+	Cycles,
+	/// Last level cache misses.
+	CacheMisses,
+	/// Mispredicted branches.
+	BranchMisses,
+	/// Time spent running on a CPU, in nanoseconds.
+	TaskClock,
+	/// Page faults.
+	PageFaults,
+	/// Context switches.
+	ContextSwitches,
+}
+
+impl Counter {
+	/// Every counter.
+	pub const ALL: [Counter; 7] = [
+		Counter::Instructions,
+		Counter::Cycles,
+		Counter::CacheMisses,
+		Counter::BranchMisses,
+		Counter::TaskClock,
+		Counter::PageFaults,
+		Counter::ContextSwitches,
+	];
+
+	/// The name of the counter, as used in zone text.
+	pub const fn name(self) -> &'static str {
+		match self {
+			Counter::Instructions => "instructions",
+			Counter::Cycles => "cycles",
+			Counter::CacheMisses => "cache_misses",
+			Counter::BranchMisses => "branch_misses",
+			Counter::TaskClock => "task_clock",
+			Counter::PageFaults => "page_faults",
+			Counter::ContextSwitches => "context_switches",
+		}
+	}
+
+	const fn bit(self) -> u8 { 1 << self as u8 }
+}
+
+static COUNTERS: AtomicU8 = AtomicU8::new(u8::MAX);
+
+/// Set the counters read by zones. Threads reopen their counters the next time a zone reads them. All counters are
+/// read by default.
+pub fn set_counters(counters: &[Counter]) {
+	let mask = counters.iter().fold(0, |mask, counter| mask | counter.bit());
+	COUNTERS.store(mask, Ordering::Relaxed);
+}
+
+/// The counters that could be opened on the current thread.
+pub fn available() -> Vec<Counter> {
+	#[cfg(all(feature = "perf-counters", feature = "enable", target_os = "linux"))]
+	return {
+		let available = imp::with_counters(|counters| counters.available());
+		Counter::ALL
+			.into_iter()
+			.filter(|counter| available & counter.bit() != 0)
+			.collect()
+	};
+
+	#[cfg(not(all(feature = "perf-counters", feature = "enable", target_os = "linux")))]
+	Vec::new()
+}
+
+/// The values of the counters of the current thread.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Reading {
+	available: u8,
+	values: [u64; Counter::ALL.len()],
+}
+
+impl Reading {
+	/// Read the counters of the current thread.
+	#[inline(always)]
+	pub fn now() -> Self {
+		#[cfg(all(feature = "perf-counters", feature = "enable", target_os = "linux"))]
+		return imp::with_counters(|counters| counters.read());
+
+		#[cfg(not(all(feature = "perf-counters", feature = "enable", target_os = "linux")))]
+		Self::default()
+	}
+
+	/// The value of a counter, if it is available.
+	pub fn get(&self, counter: Counter) -> Option<u64> {
+		(self.available & counter.bit() != 0).then(|| self.values[counter as usize])
+	}
+
+	/// The difference between this reading and an earlier one. Only counters available in both are kept.
+	pub fn since(&self, earlier: &Reading) -> Reading {
+		let mut values = [0; Counter::ALL.len()];
+		for (i, value) in values.iter_mut().enumerate() {
+			*value = self.values[i].wrapping_sub(earlier.values[i]);
+		}
+		Reading {
+			available: self.available & earlier.available,
+			values,
+		}
+	}
+}
+
+impl fmt::Display for Reading {
+	/// Formats the available counters as space-separated `name=value` pairs.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let mut first = true;
+		for counter in Counter::ALL {
+			if let Some(value) = self.get(counter) {
+				if !first {
+					f.write_str(" ")?;
+				}
+				first = false;
+				write!(f, "{}={}", counter.name(), value)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A zone that attaches the difference of the counters of its thread between its beginning and end as text.
+pub struct CountedZone<Z: ZoneGuard = Zone> {
+	zone: Z,
+	start: Option<Reading>,
+}
+
+impl<Z: ZoneGuard> CountedZone<Z> {
+	/// Start counting for `zone`. Counters are not read if the zone is inactive.
+	#[inline(always)]
+	pub fn new(zone: Z) -> Self {
+		let start = zone.is_active().then(Reading::now);
+		Self { zone, start }
+	}
+}
+
+impl<Z: ZoneGuard> Deref for CountedZone<Z> {
+	type Target = Z;
+
+	fn deref(&self) -> &Z { &self.zone }
+}
+
+impl<Z: ZoneGuard> ZoneGuard for CountedZone<Z> {
+	#[inline(always)]
+	fn inactive() -> Self {
+		Self {
+			zone: Z::inactive(),
+			start: None,
+		}
+	}
+
+	#[inline(always)]
+	fn is_active(&self) -> bool { self.zone.is_active() }
+
+	#[inline(always)]
+	fn text_fmt(&self, args: fmt::Arguments) { self.zone.text_fmt(args) }
+}
+
+impl<Z: ZoneGuard> Drop for CountedZone<Z> {
+	#[inline(always)]
+	fn drop(&mut self) {
+		if let Some(start) = self.start {
+			let delta = Reading::now().since(&start);
+			if delta.available != 0 {
+				self.zone.text_fmt(format_args!("{}", delta));
+			}
+		}
+	}
+}
+
+#[cfg(all(feature = "perf-counters", feature = "enable", target_os = "linux"))]
+mod imp {
+	use std::{
+		cell::RefCell,
+		os::fd::{AsRawFd, FromRawFd, OwnedFd},
+		sync::atomic::Ordering,
+	};
+
+	use super::{Counter, Reading, COUNTERS};
+
+	const PERF_TYPE_HARDWARE: u32 = 0;
+	const PERF_TYPE_SOFTWARE: u32 = 1;
+	const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+	const EXCLUDE_KERNEL: u64 = 1 << 5;
+	const EXCLUDE_HV: u64 = 1 << 6;
+
+	/// `perf_event_attr`, up to `PERF_ATTR_SIZE_VER5`.
+	#[repr(C)]
+	#[derive(Default)]
+	struct PerfEventAttr {
+		type_: u32,
+		size: u32,
+		config: u64,
+		sample_period: u64,
+		sample_type: u64,
+		read_format: u64,
+		flags: u64,
+		wakeup_events: u32,
+		bp_type: u32,
+		config1: u64,
+		config2: u64,
+		branch_sample_type: u64,
+		sample_regs_user: u64,
+		sample_stack_user: u32,
+		clockid: i32,
+		sample_regs_intr: u64,
+		aux_watermark: u32,
+		sample_max_stack: u16,
+		reserved: u16,
+	}
+
+	pub(super) struct ThreadCounters {
+		mask: u8,
+		fds: [Option<OwnedFd>; Counter::ALL.len()],
+	}
+
+	impl ThreadCounters {
+		fn open(mask: u8) -> Self {
+			Self {
+				mask,
+				fds: Counter::ALL.map(|counter| (mask & counter.bit() != 0).then(|| open(counter)).flatten()),
+			}
+		}
+
+		pub(super) fn available(&self) -> u8 {
+			Counter::ALL
+				.into_iter()
+				.filter(|&counter| self.fds[counter as usize].is_some())
+				.fold(0, |mask, counter| mask | counter.bit())
+		}
+
+		pub(super) fn read(&self) -> Reading {
+			let mut reading = Reading::default();
+			for counter in Counter::ALL {
+				let Some(fd) = &self.fds[counter as usize] else {
+					continue;
+				};
+				let mut value = 0u64;
+				let read = unsafe { libc::read(fd.as_raw_fd(), &mut value as *mut u64 as *mut _, 8) };
+				if read == 8 {
+					reading.values[counter as usize] = value;
+					reading.available |= counter.bit();
+				}
+			}
+			reading
+		}
+	}
+
+	thread_local! {
+		static THREAD_COUNTERS: RefCell<Option<ThreadCounters>> = const { RefCell::new(None) };
+	}
+
+	pub(super) fn with_counters<R: Default>(f: impl FnOnce(&ThreadCounters) -> R) -> R {
+		THREAD_COUNTERS
+			.try_with(|counters| {
+				let mask = COUNTERS.load(Ordering::Relaxed);
+				let mut counters = counters.borrow_mut();
+				let counters = match &mut *counters {
+					Some(counters) if counters.mask == mask => counters,
+					counters => counters.insert(ThreadCounters::open(mask)),
+				};
+				f(counters)
+			})
+			.unwrap_or_default()
+	}
+
+	fn open(counter: Counter) -> Option<OwnedFd> {
+		let (type_, config) = match counter {
+			Counter::Instructions => (PERF_TYPE_HARDWARE, 1),
+			Counter::Cycles => (PERF_TYPE_HARDWARE, 0),
+			Counter::CacheMisses => (PERF_TYPE_HARDWARE, 3),
+			Counter::BranchMisses => (PERF_TYPE_HARDWARE, 5),
+			Counter::TaskClock => (PERF_TYPE_SOFTWARE, 1),
+			Counter::PageFaults => (PERF_TYPE_SOFTWARE, 2),
+			Counter::ContextSwitches => (PERF_TYPE_SOFTWARE, 3),
+		};
+		// Context switches only happen in the kernel, so excluding it would always count 0.
+		let flags = match counter {
+			Counter::ContextSwitches => EXCLUDE_HV,
+			_ => EXCLUDE_KERNEL | EXCLUDE_HV,
+		};
+		let attr = PerfEventAttr {
+			type_,
+			size: std::mem::size_of::<PerfEventAttr>() as _,
+			config,
+			flags,
+			..Default::default()
+		};
+
+		// Count the calling thread on any CPU.
+		let fd = unsafe { libc::syscall(libc::SYS_perf_event_open, &attr, 0, -1, -1, PERF_FLAG_FD_CLOEXEC) };
+		(fd >= 0).then(|| unsafe { OwnedFd::from_raw_fd(fd as _) })
+	}
+}
@@ -27,12 +27,14 @@ impl<'a> Plotter<'a> {
 		}
 	}
 
-	/// Emit a value for the plotter.
+	/// Emit a value for the plotter, unless instrumentation is disabled.
 	#[inline(always)]
 	pub fn value(&self, value: f64) {
 		#[cfg(feature = "enable")]
-		unsafe {
-			sys::___tracy_emit_plot(self.name.as_ptr(), value);
+		if crate::instrumentation_enabled() {
+			unsafe {
+				sys::___tracy_emit_plot(self.name.as_ptr(), value);
+			}
 		}
 	}
 }
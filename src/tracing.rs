@@ -1,18 +1,37 @@
-use std::{any::TypeId, borrow::Cow, cell::UnsafeCell, num::NonZeroU64};
+use std::{
+	any::TypeId,
+	borrow::Cow,
+	cell::UnsafeCell,
+	num::NonZeroU64,
+	sync::atomic::{AtomicU32, Ordering},
+};
 
-use tracing::{span::Attributes, Id, Subscriber};
+use tracing::{span::Attributes, Event, Id, Level, Subscriber};
 use tracing_subscriber::{
-	fmt::{format::DefaultFields, FormatFields, FormattedFields},
+	fmt::{
+		format::{DefaultFields, Writer},
+		FormatFields,
+		FormattedFields,
+	},
 	layer::Context,
 	registry::LookupSpan,
 	Layer,
 };
 
+use crate::color::Color;
+
 thread_local! {
-	static STACK: UnsafeCell<Vec<u32>> = UnsafeCell::new(Vec::new());
+	static STACK: UnsafeCell<Vec<Option<u32>>> = const { UnsafeCell::new(Vec::new()) };
+}
+
+static ERROR_CALLSTACK: AtomicU32 = AtomicU32::new(0);
+
+/// Capture a callstack of `depth` frames with the messages of `ERROR` events. No callstack is captured by default.
+pub fn set_error_callstack(depth: u32) {
+	ERROR_CALLSTACK.store(crate::clamp_callstack_depth(depth), Ordering::Relaxed);
 }
 
-/// A tracing layer that tracks spans.
+/// A tracing layer that tracks spans, and sends events as messages colored by level.
 pub struct TracyLayer;
 
 impl<S> Layer<S> for TracyLayer
@@ -40,17 +59,50 @@ where
 		}
 	}
 
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		#[cfg(feature = "enable")]
+		{
+			if !crate::instrumentation_enabled() {
+				return;
+			}
+
+			let meta = event.metadata();
+			let mut text = format!("{}: ", meta.target());
+			if DefaultFields::default()
+				.format_fields(Writer::new(&mut text), event)
+				.is_err()
+			{
+				return;
+			}
+
+			let (color, depth) = match *meta.level() {
+				Level::ERROR => (Some(Color::RED), ERROR_CALLSTACK.load(Ordering::Relaxed)),
+				Level::WARN => (Some(Color::YELLOW), 0),
+				Level::INFO => (Some(Color::GREEN), 0),
+				Level::DEBUG => (Some(Color::CYAN), 0),
+				Level::TRACE => (None, 0),
+			};
+			// Emitted on the current thread, so the message lands inside the entered span's zone.
+			crate::message::message(&text, color, depth);
+		}
+	}
+
 	fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
 		#[cfg(feature = "enable")]
 		{
 			let Some(span) = ctx.span(id) else {
 				return;
 			};
+			if !crate::instrumentation_enabled() {
+				// Keep the stack balanced, so that the matching exit ends nothing.
+				STACK.with(|stack| unsafe { (*stack.get()).push(None) });
+				return;
+			}
 			let meta = span.metadata();
 			let file = meta.file().unwrap_or("");
 			let module = meta.module_path().unwrap_or("");
 			let name: Cow<str> = if let Some(fields) = span.extensions().get::<FormattedFields<DefaultFields>>() {
-				if fields.fields.as_str().is_empty() {
+				if fields.fields.is_empty() {
 					meta.name().into()
 				} else {
 					format!("{}{{{}}}", meta.name(), fields.fields.as_str()).into()
@@ -75,7 +127,7 @@ where
 
 				STACK.with(|stack| {
 					let stack = &mut *stack.get();
-					stack.push(ctx.id);
+					stack.push(Some(ctx.id));
 				})
 			}
 		}
@@ -86,10 +138,9 @@ where
 		{
 			STACK.with(|stack| unsafe {
 				let stack = &mut *stack.get();
-				sys::___tracy_emit_zone_end(sys::___tracy_c_zone_context {
-					id: stack.pop().unwrap(),
-					active: 1,
-				})
+				if let Some(id) = stack.pop().unwrap() {
+					sys::___tracy_emit_zone_end(sys::___tracy_c_zone_context { id, active: 1 })
+				}
 			});
 		}
 	}
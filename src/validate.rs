@@ -0,0 +1,67 @@
+//! Runtime checks of the invariants that the profiler relies on, enabled by the `validate` feature.
+//!
+//! The following are checked:
+//! - Zones end in the reverse order they began in, on the thread that began them.
+//! - Zones end in the same fiber they began in.
+//! - Discontinuous frames with the same name do not overlap.
+//!
+//! Violations panic, naming the zones or frames involved. If the thread is already panicking, they are printed to
+//! stderr instead.
+
+use std::{
+	borrow::Cow,
+	ffi::{c_char, CStr},
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Mutex,
+	},
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+static FRAMES: Mutex<Vec<&'static CStr>> = Mutex::new(Vec::new());
+
+/// Report a violation.
+#[cold]
+pub(crate) fn violation(error: String) {
+	if std::thread::panicking() {
+		eprintln!("tracy_full: {}", error);
+	} else {
+		panic!("{}", error);
+	}
+}
+
+/// A unique id for a zone.
+#[inline(always)]
+pub(crate) fn next_id() -> u64 { NEXT_ID.fetch_add(1, Ordering::Relaxed) }
+
+/// A description of a fiber returned by [`crate::current_fiber`].
+pub(crate) fn fiber_name(fiber: *const c_char) -> Cow<'static, str> {
+	if fiber.is_null() {
+		"no fiber".into()
+	} else {
+		format!("fiber `{}`", unsafe { CStr::from_ptr(fiber) }.to_string_lossy()).into()
+	}
+}
+
+/// Record the start of a discontinuous frame.
+pub(crate) fn frame_start(name: &'static CStr) {
+	let mut frames = FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+	if frames.contains(&name) {
+		drop(frames);
+		violation(format!(
+			"discontinuous frame `{}` started while another frame with the same name is active",
+			name.to_string_lossy()
+		));
+	} else {
+		frames.push(name);
+	}
+}
+
+/// Record the end of a discontinuous frame.
+pub(crate) fn frame_end(name: &'static CStr) {
+	let mut frames = FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+	if let Some(index) = frames.iter().position(|&frame| frame == name) {
+		frames.remove(index);
+	}
+}
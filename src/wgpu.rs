@@ -4,7 +4,6 @@ use std::{
 	mem::{ManuallyDrop, MaybeUninit},
 	ops::{Deref, DerefMut},
 	pin::Pin,
-	sync::atomic::{AtomicU8, Ordering},
 	time::{Duration, Instant},
 };
 
@@ -67,19 +66,6 @@ macro_rules! wgpu_compute_pass {
 	}};
 }
 
-#[cfg(feature = "enable")]
-static CONTEXTS: AtomicU8 = AtomicU8::new(0);
-
-#[cfg(feature = "enable")]
-fn get_next_context() -> u8 {
-	let next = CONTEXTS.fetch_add(1, Ordering::Relaxed);
-	if next == 255 {
-		panic!("Too many contexts");
-	}
-
-	next
-}
-
 #[cfg(feature = "enable")]
 struct QueryPool {
 	resolve: Buffer,
@@ -201,7 +187,7 @@ impl ProfileContext {
 	) -> Self {
 		#[cfg(feature = "enable")]
 		{
-			let context = get_next_context();
+			let context = crate::next_gpu_context();
 			let mut allocated_query_ids = 0;
 
 			let frames = if enabled {
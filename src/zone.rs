@@ -1,104 +1,1810 @@
 use std::{
+	cell::RefCell,
+	collections::{BTreeMap, HashMap},
 	ffi::{CStr, CString},
+	fmt::{self, Write},
 	marker::PhantomData,
+	ops::Deref,
+	sync::{
+		atomic::{AtomicBool, AtomicPtr, AtomicU16, AtomicU32, AtomicU64, AtomicU8, Ordering},
+		Mutex,
+		OnceLock,
+	},
+	time::{Duration, Instant},
 };
 
-use crate::color::Color;
+use crate::{callsite, color::Color};
 
 /// Mark a zone in the current scope.
+///
+/// Use `zone!(let name, ...)` to bind the zone to `name`, so that it can be annotated at runtime.
+///
+/// # Example
+/// ```
+/// # use tracy_full::zone;
+/// zone!("Load");
+///
+/// zone!(let zone, "Process");
+/// zone.text("assets/texture.png");
+/// zone.value(42);
+///
+/// // Zone with a name computed at runtime.
+/// let job = String::from("Job 1");
+/// zone!(dynamic &job);
+///
+/// // Zone with fields, see `zone_fields!`.
+/// let path = "assets/texture.png";
+/// zone!("Load", path = %path, size = 1024);
+///
+/// // Zone that is compiled out if the maximum level is lower than `debug`.
+/// zone!(level = debug, "Fine");
+///
+/// // Zones for hot paths, see `Sampler`.
+/// zone!(every = 100, "Every 100th");
+/// zone!(limit = 10 per frame, "At most 10 per frame");
+/// zone!(limit = 10 per second, "At most 10 per second");
+///
+/// // Zone that is only emitted if it lasts at least 100us, see `DeferredZone`.
+/// zone!("Read", min = 100us);
+///
+/// // Zone that emits a message if it takes longer than 2ms, see `BudgetZone`.
+/// zone!("Physics", budget = 2ms);
+///
+/// // Zone that counts how many times it ran in each frame, see `CallCounter`.
+/// zone!(count, "Pathfinding");
+///
+/// // Zone with the performance counters of the thread attached, see `perf`. Cannot be combined with `min`.
+/// zone!(perf, "Simulate");
+/// ```
 #[macro_export]
 macro_rules! zone {
-	() => {
-		let loc = $crate::get_location!();
-		let _zone = $crate::zone::zone(loc, true);
+	(@level error) => { $crate::zone::Level::ERROR };
+	(@level warn) => { $crate::zone::Level::WARN };
+	(@level info) => { $crate::zone::Level::INFO };
+	(@level debug) => { $crate::zone::Level::DEBUG };
+	(@level trace) => { $crate::zone::Level::TRACE };
+	(@level $level:ident) => {
+		compile_error!("expected one of `error`, `warn`, `info`, `debug`, or `trace`")
 	};
 
-	($name:literal $(,)?) => {
-		let loc = $crate::get_location!($name);
-		let _zone = $crate::zone::zone(loc, true);
+	(@guard perf $(, $($args:tt)*)?) => {
+		$crate::zone!(@perf [$($($args)*)?] $($($args)*)?)
 	};
 
-	($color:expr $(,)?) => {
-		let loc = $crate::get_location!($color);
-		let _zone = $crate::zone::zone(loc, true);
+	// Deferred zones have no text to attach the counters to.
+	(@perf [$($args:tt)*] min = $($rest:tt)*) => {
+		compile_error!("`perf` cannot be combined with `min`, because deferred zones have no text")
 	};
+	(@perf [$($args:tt)*] $head:tt $($rest:tt)*) => {
+		$crate::zone!(@perf [$($args)*] $($rest)*)
+	};
+	(@perf [$($args:tt)*]) => {
+		$crate::perf::CountedZone::new($crate::zone!(@guard $($args)*))
+	};
+
+	(@guard count, $name:literal $(, $($args:tt)*)?) => {{
+		static COUNTER: $crate::zone::CallCounter = $crate::zone::CallCounter::new(unsafe {
+			::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!($name, " count\0").as_bytes())
+		});
+		COUNTER.increment();
+		$crate::zone!(@guard $name $(, $($args)*)?)
+	}};
+
+	(@guard level = $level:ident $(, $($args:tt)*)?) => {
+		if $crate::zone!(@level $level).is_enabled() {
+			$crate::zone!(@guard $($($args)*)?)
+		} else {
+			$crate::zone::ZoneGuard::inactive()
+		}
+	};
+
+	(@guard every = $n:expr $(, $($args:tt)*)?) => {{
+		static SAMPLER: $crate::zone::Sampler = $crate::zone::Sampler::every($n);
+		if SAMPLER.sample() {
+			$crate::zone!(@guard $($($args)*)?)
+		} else {
+			$crate::zone::ZoneGuard::inactive()
+		}
+	}};
+
+	(@guard limit = $k:literal per frame $(, $($args:tt)*)?) => {{
+		static SAMPLER: $crate::zone::Sampler = $crate::zone::Sampler::per_frame($k);
+		if SAMPLER.sample() {
+			$crate::zone!(@guard $($($args)*)?)
+		} else {
+			$crate::zone::ZoneGuard::inactive()
+		}
+	}};
+
+	(@guard limit = $k:literal per second $(, $($args:tt)*)?) => {{
+		static SAMPLER: $crate::zone::Sampler = $crate::zone::Sampler::per_second($k);
+		if SAMPLER.sample() {
+			$crate::zone!(@guard $($($args)*)?)
+		} else {
+			$crate::zone::ZoneGuard::inactive()
+		}
+	}};
+
+	(@guard budget = $budget:tt $(,)?) => {{
+		const BUDGET: u64 = $crate::zone::duration_nanos(stringify!($budget));
+		$crate::zone::zone_budget($crate::get_location!(), BUDGET, true)
+	}};
+
+	(@guard min = $min:tt $(,)?) => {{
+		const MIN: u64 = $crate::zone::duration_nanos(stringify!($min));
+		$crate::zone::zone_min($crate::get_location!(), MIN, true)
+	}};
+
+	(@guard $key:ident = $($fields:tt)*) => {{
+		let zone = $crate::zone!(@guard);
+		$crate::zone_fields!(zone, $key = $($fields)*);
+		zone
+	}};
+
+	(@guard $name:literal, budget = $budget:tt $(,)?) => {{
+		const BUDGET: u64 = $crate::zone::duration_nanos(stringify!($budget));
+		$crate::zone::zone_budget($crate::get_location!($name), BUDGET, true)
+	}};
+
+	(@guard $name:literal, min = $min:tt $(,)?) => {{
+		const MIN: u64 = $crate::zone::duration_nanos(stringify!($min));
+		$crate::zone::zone_min($crate::get_location!($name), MIN, true)
+	}};
+
+	(@guard $name:literal, $key:ident = $($fields:tt)*) => {{
+		let zone = $crate::zone!(@guard $name);
+		$crate::zone_fields!(zone, $key = $($fields)*);
+		zone
+	}};
+
+	(@guard $name:literal, $enabled:expr, budget = $budget:tt $(,)?) => {{
+		const BUDGET: u64 = $crate::zone::duration_nanos(stringify!($budget));
+		$crate::zone::zone_budget($crate::get_location!($name), BUDGET, $enabled)
+	}};
+
+	(@guard $name:literal, $enabled:expr, min = $min:tt $(,)?) => {{
+		const MIN: u64 = $crate::zone::duration_nanos(stringify!($min));
+		$crate::zone::zone_min($crate::get_location!($name), MIN, $enabled)
+	}};
+
+	(@guard $name:literal, $enabled:expr, $key:ident = $($fields:tt)*) => {{
+		let zone = $crate::zone!(@guard $name, $enabled);
+		$crate::zone_fields!(zone, $key = $($fields)*);
+		zone
+	}};
+
+	(@guard $name:literal, $color:expr, $enabled:expr, budget = $budget:tt $(,)?) => {{
+		const BUDGET: u64 = $crate::zone::duration_nanos(stringify!($budget));
+		$crate::zone::zone_budget($crate::get_location!($name, $color), BUDGET, $enabled)
+	}};
+
+	(@guard $name:literal, $color:expr, $enabled:expr, min = $min:tt $(,)?) => {{
+		const MIN: u64 = $crate::zone::duration_nanos(stringify!($min));
+		$crate::zone::zone_min($crate::get_location!($name, $color), MIN, $enabled)
+	}};
+
+	(@guard $name:literal, $color:expr, $enabled:expr, $key:ident = $($fields:tt)*) => {{
+		let zone = $crate::zone!(@guard $name, $color, $enabled);
+		$crate::zone_fields!(zone, $key = $($fields)*);
+		zone
+	}};
+
+	(@guard dynamic $name:expr $(,)?) => {
+		$crate::zone::zone_named($crate::get_location!(), $name, true)
+	};
+
+	(@guard dynamic $name:expr, $enabled:expr $(,)?) => {
+		$crate::zone::zone_named($crate::get_location!(), $name, $enabled)
+	};
+
+	(@guard dynamic $name:expr, $color:expr, $enabled:expr $(,)?) => {
+		$crate::zone::zone_named($crate::get_location!($color), $name, $enabled)
+	};
+
+	(@guard) => {
+		$crate::zone::zone($crate::get_location!(), true)
+	};
+
+	(@guard $name:literal $(,)?) => {
+		$crate::zone::zone($crate::get_location!($name), true)
+	};
+
+	(@guard $color:expr $(,)?) => {
+		$crate::zone::zone($crate::get_location!($color), true)
+	};
+
+	(@guard $name:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone($crate::get_location!($name), $enabled)
+	};
+
+	(@guard $color:expr, $enabled:expr $(,)?) => {
+		$crate::zone::zone($crate::get_location!($color), $enabled)
+	};
+
+	(@guard $name:literal, $color:expr, $enabled:expr $(,)?) => {
+		$crate::zone::zone($crate::get_location!($name, $color), $enabled)
+	};
+
+	(@guard $($args:tt)*) => {
+		compile_error!("invalid arguments to `zone!`")
+	};
+
+	(let $zone:ident $(, $($args:tt)*)?) => {
+		let $zone = $crate::zone!(@guard $($($args)*)?);
+	};
+
+	($($args:tt)*) => {
+		let _zone = $crate::zone!(@guard $($args)*);
+	};
+}
+
+/// Mark an instant on the timeline of the current thread, with an optional formatted payload.
+///
+/// Markers are zones that end as soon as they begin, so they appear in zone statistics and searches.
+///
+/// # Example
+/// ```
+/// # use tracy_full::{color::Color, marker};
+/// let bytes = 1024;
+/// marker!("GC");
+/// marker!("Level loaded", Color::GREEN);
+/// marker!("Packet received", "{} bytes", bytes);
+/// marker!("Packet received", Color::BLUE, "{} bytes", bytes);
+/// ```
+#[macro_export]
+macro_rules! marker {
+	($name:literal $(,)?) => {{
+		let _zone = $crate::zone::zone($crate::get_location!($name), true);
+	}};
+
+	($name:literal, $fmt:literal $(, $($args:tt)*)?) => {{
+		let zone = $crate::zone::zone($crate::get_location!($name), true);
+		$crate::zone_text!(zone, $fmt $(, $($args)*)?);
+	}};
+
+	($name:literal, $color:expr $(,)?) => {{
+		let _zone = $crate::zone::zone($crate::get_location!($name, $color), true);
+	}};
+
+	($name:literal, $color:expr, $fmt:literal $(, $($args:tt)*)?) => {{
+		let zone = $crate::zone::zone($crate::get_location!($name, $color), true);
+		$crate::zone_text!(zone, $fmt $(, $($args)*)?);
+	}};
+}
+
+/// Attach formatted text to a zone, without allocating.
+///
+/// Nothing is formatted if the zone is inactive. Text longer than [`Zone::MAX_TEXT_LEN`] is truncated.
+///
+/// # Example
+/// ```
+/// # use tracy_full::{zone, zone_text};
+/// zone!(let zone, "Load");
+/// zone_text!(zone, "loading {} of {}", 1, 10);
+/// ```
+#[macro_export]
+macro_rules! zone_text {
+	($zone:expr, $($arg:tt)+) => {{
+		let zone: &$crate::zone::Zone = &$zone;
+		if zone.is_active() {
+			zone.text_fmt(format_args!($($arg)+));
+		}
+	}};
+}
+
+/// Attach structured fields to a zone, as zone text in a `key=value key=value` layout.
+///
+/// `key = value` and `key = %value` use the `Display` implementation of the value, and `key = ?value` uses the
+/// `Debug` implementation. A field named `value` is not formatted, and sets the zone value instead.
+///
+/// Nothing is formatted if the zone is inactive.
+///
+/// # Example
+/// ```
+/// # use tracy_full::{zone, zone_fields};
+/// let path = "assets/texture.png";
+/// let size = 1024;
+/// zone!(let zone, "Load");
+/// zone_fields!(zone, path = %path, size = size, kind = ?Some("texture"), value = size);
+/// ```
+#[macro_export]
+macro_rules! zone_fields {
+	(@munch $zone:ident [$($fields:tt)*] [$($value:tt)*] value = $v:expr $(, $($rest:tt)*)?) => {
+		$crate::zone_fields!(@munch $zone [$($fields)*] [$v] $($($rest)*)?)
+	};
+
+	(@munch $zone:ident [$($fields:tt)*] [$($value:tt)*] $key:ident = ?$v:expr $(, $($rest:tt)*)?) => {
+		$crate::zone_fields!(
+			@munch $zone
+			[$($fields)* (stringify!($key), &$crate::zone::DebugField(&$v) as &dyn ::std::fmt::Display),]
+			[$($value)*]
+			$($($rest)*)?
+		)
+	};
+
+	(@munch $zone:ident [$($fields:tt)*] [$($value:tt)*] $key:ident = %$v:expr $(, $($rest:tt)*)?) => {
+		$crate::zone_fields!(
+			@munch $zone
+			[$($fields)* (stringify!($key), &$v as &dyn ::std::fmt::Display),]
+			[$($value)*]
+			$($($rest)*)?
+		)
+	};
+
+	(@munch $zone:ident [$($fields:tt)*] [$($value:tt)*] $key:ident = $v:expr $(, $($rest:tt)*)?) => {
+		$crate::zone_fields!(
+			@munch $zone
+			[$($fields)* (stringify!($key), &$v as &dyn ::std::fmt::Display),]
+			[$($value)*]
+			$($($rest)*)?
+		)
+	};
+
+	(@munch $zone:ident [$($fields:tt)*] [$($value:expr)?]) => {
+		if $zone.is_active() {
+			$crate::zone::Fields(&[$($fields)*]).attach($zone);
+			$($zone.value($value as u64);)?
+		}
+	};
+
+	($zone:expr, $($fields:tt)+) => {{
+		let zone: &$crate::zone::Zone = &$zone;
+		$crate::zone_fields!(@munch zone [] [] $($fields)+)
+	}};
+}
+
+/// Mark a zone in the current scope, sampling the callstack.
+///
+/// Accepts the same forms as [`zone!`], with the callstack depth after the name and color.
+///
+/// # Example
+/// ```
+/// # use tracy_full::zone_sample;
+/// zone_sample!(8);
+/// zone_sample!("Load", 8, true);
+///
+/// zone_sample!(let zone, "Process", 8, true);
+/// zone.value(42);
+/// ```
+#[macro_export]
+macro_rules! zone_sample {
+	(@guard level = $level:ident, $($args:tt)*) => {
+		if $crate::zone!(@level $level).is_enabled() {
+			$crate::zone_sample!(@guard $($args)*)
+		} else {
+			$crate::zone::ZoneGuard::inactive()
+		}
+	};
+
+	(@guard dynamic $name:expr, $depth:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone_named_sample($crate::get_location!(), $name, $crate::clamp_callstack_depth($depth), $enabled)
+	};
+
+	(@guard dynamic $name:expr, $color:expr, $depth:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone_named_sample(
+			$crate::get_location!($color),
+			$name,
+			$crate::clamp_callstack_depth($depth),
+			$enabled,
+		)
+	};
+
+	(@guard $depth:literal $(,)?) => {
+		$crate::zone::zone_sample($crate::get_location!(), $crate::clamp_callstack_depth($depth), true)
+	};
+
+	(@guard $name:literal, $depth:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone_sample(
+			$crate::get_location!($name),
+			$crate::clamp_callstack_depth($depth),
+			$enabled,
+		)
+	};
+
+	(@guard $color:expr, $depth:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone_sample(
+			$crate::get_location!($color),
+			$crate::clamp_callstack_depth($depth),
+			$enabled,
+		)
+	};
+
+	(@guard $name:literal, $color:expr, $depth:literal, $enabled:expr $(,)?) => {
+		$crate::zone::zone_sample(
+			$crate::get_location!($name, $color),
+			$crate::clamp_callstack_depth($depth),
+			$enabled,
+		)
+	};
+
+	(@guard $($args:tt)*) => {
+		compile_error!("invalid arguments to `zone_sample!`")
+	};
+
+	(let $zone:ident, $($args:tt)*) => {
+		let $zone = $crate::zone_sample!(@guard $($args)*);
+	};
+
+	($($args:tt)*) => {
+		let _zone = $crate::zone_sample!(@guard $($args)*);
+	};
+}
+
+/// The level of a zone, used to compile out zones above the maximum level.
+///
+/// The maximum level is set with the `max_level_*` features. If several are enabled, the lowest one is used. Zones
+/// without a level are always compiled in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Level(u8);
+
+impl Level {
+	pub const DEBUG: Level = Level(4);
+	pub const ERROR: Level = Level(1);
+	pub const INFO: Level = Level(3);
+	pub const OFF: Level = Level(0);
+	pub const TRACE: Level = Level(5);
+	pub const WARN: Level = Level(2);
+
+	/// Whether zones of this level are compiled in.
+	#[inline(always)]
+	pub const fn is_enabled(self) -> bool { self.0 <= STATIC_MAX_LEVEL.0 }
+}
+
+/// The maximum level of zones that are compiled in.
+pub const STATIC_MAX_LEVEL: Level = if cfg!(feature = "max_level_off") {
+	Level::OFF
+} else if cfg!(feature = "max_level_error") {
+	Level::ERROR
+} else if cfg!(feature = "max_level_warn") {
+	Level::WARN
+} else if cfg!(feature = "max_level_info") {
+	Level::INFO
+} else if cfg!(feature = "max_level_debug") {
+	Level::DEBUG
+} else {
+	Level::TRACE
+};
+
+/// A per-callsite counter that decides which zones to emit on very hot paths.
+///
+/// Used by `zone!(every = n, ...)`, `zone!(limit = k per frame, ...)`, and `zone!(limit = k per second, ...)`. Zones
+/// that are not sampled are inactive, and cost about as much as a disabled zone.
+pub struct Sampler {
+	mode: SamplerMode,
+	count: AtomicU64,
+	window: AtomicU64,
+}
+
+enum SamplerMode {
+	Every(u64),
+	PerFrame(u64),
+	PerSecond(u64),
+}
+
+impl Sampler {
+	/// Emit every `n`th zone, starting with the first.
+	pub const fn every(n: u64) -> Self { Self::new(SamplerMode::Every(if n == 0 { 1 } else { n })) }
+
+	/// Emit at most `k` zones per main frame. See [`crate::frame::frame`].
+	pub const fn per_frame(k: u64) -> Self { Self::new(SamplerMode::PerFrame(k)) }
+
+	/// Emit at most `k` zones per second.
+	pub const fn per_second(k: u64) -> Self { Self::new(SamplerMode::PerSecond(k)) }
+
+	const fn new(mode: SamplerMode) -> Self {
+		Self {
+			mode,
+			count: AtomicU64::new(0),
+			window: AtomicU64::new(0),
+		}
+	}
+
+	/// Whether the next zone should be emitted.
+	#[inline(always)]
+	pub fn sample(&self) -> bool {
+		#[cfg(feature = "enable")]
+		{
+			match self.mode {
+				SamplerMode::Every(n) => self.count.fetch_add(1, Ordering::Relaxed).is_multiple_of(n),
+				SamplerMode::PerFrame(k) => self.limit(crate::frame::frame_count(), k),
+				SamplerMode::PerSecond(k) => {
+					static EPOCH: OnceLock<Instant> = OnceLock::new();
+					self.limit(EPOCH.get_or_init(Instant::now).elapsed().as_secs(), k)
+				},
+			}
+		}
+
+		#[cfg(not(feature = "enable"))]
+		false
+	}
+
+	/// Allow `k` zones in the window `window`. The reset at the start of a window is racy, so a few extra zones may
+	/// be emitted when many threads hit the callsite at once.
+	#[inline(always)]
+	fn limit(&self, window: u64, k: u64) -> bool {
+		if self.window.load(Ordering::Relaxed) != window {
+			self.window.store(window, Ordering::Relaxed);
+			self.count.store(0, Ordering::Relaxed);
+		}
+		self.count.fetch_add(1, Ordering::Relaxed) < k
+	}
+}
+
+/// A per-callsite counter of how many times a zone ran, plotted and reset at every [`crate::frame::frame`].
+///
+/// Used by `zone!(count, "name", ...)`, which plots the count as "name count". Zones are counted even if they are
+/// inactive.
+pub struct CallCounter {
+	#[cfg(feature = "enable")]
+	plot: &'static CStr,
+	#[cfg(feature = "enable")]
+	count: AtomicU64,
+	#[cfg(feature = "enable")]
+	registered: AtomicBool,
+	#[cfg(feature = "enable")]
+	next: AtomicPtr<CallCounter>,
+}
+
+#[cfg(feature = "enable")]
+static CALL_COUNTERS: AtomicPtr<CallCounter> = AtomicPtr::new(std::ptr::null_mut());
+
+impl CallCounter {
+	/// Create a counter that is plotted as `plot`.
+	pub const fn new(plot: &'static CStr) -> Self {
+		Self {
+			#[cfg(feature = "enable")]
+			plot,
+			#[cfg(feature = "enable")]
+			count: AtomicU64::new(0),
+			#[cfg(feature = "enable")]
+			registered: AtomicBool::new(false),
+			#[cfg(feature = "enable")]
+			next: AtomicPtr::new(std::ptr::null_mut()),
+		}
+	}
+
+	/// Count one run of the zone. Runs are not counted while instrumentation is disabled.
+	#[inline(always)]
+	pub fn increment(&'static self) {
+		#[cfg(feature = "enable")]
+		if crate::instrumentation_enabled() {
+			self.count.fetch_add(1, Ordering::Relaxed);
+			if !self.registered.load(Ordering::Relaxed) {
+				self.register();
+			}
+		}
+	}
+
+	/// The number of runs in the current frame.
+	pub fn count(&self) -> u64 {
+		#[cfg(feature = "enable")]
+		return self.count.load(Ordering::Relaxed);
+
+		#[cfg(not(feature = "enable"))]
+		0
+	}
+
+	#[cold]
+	#[cfg(feature = "enable")]
+	fn register(&'static self) {
+		if self.registered.swap(true, Ordering::Relaxed) {
+			return;
+		}
+
+		unsafe {
+			sys::___tracy_emit_plot_config(self.plot.as_ptr(), 0, 1, 1, 0);
+		}
+		let this = self as *const _ as *mut _;
+		let mut head = CALL_COUNTERS.load(Ordering::Relaxed);
+		loop {
+			self.next.store(head, Ordering::Relaxed);
+			match CALL_COUNTERS.compare_exchange_weak(head, this, Ordering::Release, Ordering::Relaxed) {
+				Ok(_) => break,
+				Err(current) => head = current,
+			}
+		}
+	}
+}
+
+/// Plot and reset every registered call counter.
+#[cfg(feature = "enable")]
+pub(crate) fn emit_call_counts() {
+	let mut next = unsafe { CALL_COUNTERS.load(Ordering::Acquire).as_ref() };
+	while let Some(counter) = next {
+		unsafe {
+			sys::___tracy_emit_plot(counter.plot.as_ptr(), counter.count.swap(0, Ordering::Relaxed) as f64);
+		}
+		next = unsafe { counter.next.load(Ordering::Relaxed).as_ref() };
+	}
+}
+
+/// Create a zone.
+#[inline(always)]
+pub fn zone(loc: &'static ZoneLocation, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		Zone::begun(
+			sys::___tracy_emit_zone_begin(
+				&loc.loc,
+				(active && crate::instrumentation_enabled() && loc.is_enabled()) as _,
+			),
+			StackLocation::Source(&loc.loc),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+/// Create a zone with a name computed at runtime.
+///
+/// The source location for each distinct name is built once and cached, so repeated names are cheap. Every distinct
+/// name is kept alive for the rest of the program: use [`zone_alloc`] for names that are unbounded. Names are cut at
+/// their first NUL.
+#[inline(always)]
+pub fn zone_named(loc: &'static ZoneLocation, name: &str, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		if !(active && crate::instrumentation_enabled() && loc.is_enabled()) {
+			return Zone::inactive();
+		}
+
+		let interned = intern_location(loc, name);
+		Zone::begun(
+			sys::___tracy_emit_zone_begin(&interned.0, 1),
+			StackLocation::Source(&interned.0),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+/// Create a callstack sampled zone with a name computed at runtime.
+///
+/// See [`zone_named`] for the caching behavior.
+#[inline(always)]
+pub fn zone_named_sample(loc: &'static ZoneLocation, name: &str, depth: u32, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		if !(active && crate::instrumentation_enabled() && loc.is_enabled()) {
+			return Zone::inactive();
+		}
+
+		let interned = intern_location(loc, name);
+		Zone::begun(
+			sys::___tracy_emit_zone_begin_callstack(&interned.0, depth as _, 1),
+			StackLocation::Source(&interned.0),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+/// Create a zone with a name computed at runtime, without caching the source location.
+///
+/// The source location is sent to the profiler with every zone, which is more expensive than [`zone_named`], but does
+/// not keep anything alive.
+#[inline(always)]
+pub fn zone_alloc(loc: &'static ZoneLocation, name: &str, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		if !(active && crate::instrumentation_enabled() && loc.is_enabled()) {
+			return Zone::inactive();
+		}
+
+		Zone::begun(
+			sys::___tracy_emit_zone_begin_alloc(alloc_location(loc, Some(name), loc.loc.color), 1),
+			StackLocation::Source(&loc.loc),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+/// Create a callstack sampled zone with a name computed at runtime, without caching the source location.
+#[inline(always)]
+pub fn zone_alloc_sample(loc: &'static ZoneLocation, name: &str, depth: u32, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		if !(active && crate::instrumentation_enabled() && loc.is_enabled()) {
+			return Zone::inactive();
+		}
+
+		Zone::begun(
+			sys::___tracy_emit_zone_begin_alloc_callstack(
+				alloc_location(loc, Some(name), loc.loc.color),
+				depth as _,
+				1,
+			),
+			StackLocation::Source(&loc.loc),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+#[cfg(feature = "enable")]
+struct InternedLocation(sys::___tracy_source_location_data);
+
+#[cfg(feature = "enable")]
+unsafe impl Send for InternedLocation {}
+#[cfg(feature = "enable")]
+unsafe impl Sync for InternedLocation {}
+
+#[cfg(feature = "enable")]
+static INTERNED: Mutex<BTreeMap<usize, HashMap<Box<str>, &'static InternedLocation>>> = Mutex::new(BTreeMap::new());
+
+#[cfg(feature = "enable")]
+thread_local! {
+	static INTERNED_CACHE: RefCell<HashMap<usize, HashMap<Box<str>, &'static InternedLocation>>> =
+		RefCell::new(HashMap::new());
+}
+
+/// Get the source location for `name` at `loc`, creating it if it doesn't exist yet.
+///
+/// Each thread has its own cache, so that the global lock is only taken the first time a thread sees a name.
+#[cfg(feature = "enable")]
+fn intern_location(loc: &'static ZoneLocation, name: &str) -> &'static InternedLocation {
+	intern(loc as *const _ as usize, name, || loc.loc)
+}
+
+/// Get the source location for `name` at the location of a caller, creating it if it doesn't exist yet.
+#[cfg(feature = "enable")]
+fn intern_caller(location: &'static std::panic::Location<'static>, name: &str) -> &'static InternedLocation {
+	intern(location as *const _ as usize, name, || {
+		sys::___tracy_source_location_data {
+			name: std::ptr::null(),
+			function: c"".as_ptr(),
+			file: CString::new(location.file()).unwrap_or_default().into_raw(),
+			line: location.line(),
+			color: 0,
+		}
+	})
+}
+
+/// Get the source location for `name` in the locations of `key`, creating it from `base` if it doesn't exist yet.
+#[cfg(feature = "enable")]
+fn intern(
+	key: usize, name: &str, base: impl FnOnce() -> sys::___tracy_source_location_data,
+) -> &'static InternedLocation {
+	INTERNED_CACHE.with(|cache| {
+		let mut cache = cache.borrow_mut();
+		let names = cache.entry(key).or_default();
+		if let Some(interned) = names.get(name) {
+			return *interned;
+		}
+
+		let interned = *INTERNED
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.entry(key)
+			.or_default()
+			.entry(name.into())
+			.or_insert_with(|| {
+				// C strings end at the first NUL, so cut the name there rather than panicking.
+				let name = name.split('\0').next().unwrap_or_default();
+				let name = CString::new(name).unwrap_or_default();
+				Box::leak(Box::new(InternedLocation(sys::___tracy_source_location_data {
+					name: name.into_raw(),
+					..base()
+				})))
+			});
+		names.insert(name.into(), interned);
+		interned
+	})
+}
+
+/// Allocate a source location for `name` at `loc`, to be consumed by a `___tracy_emit_zone_begin_alloc*` call.
+#[cfg(feature = "enable")]
+unsafe fn alloc_location(loc: &'static ZoneLocation, name: Option<&str>, color: u32) -> u64 {
+	alloc_srcloc(
+		loc.loc.line,
+		CStr::from_ptr(loc.loc.file).to_bytes(),
+		CStr::from_ptr(loc.loc.function).to_bytes(),
+		name,
+		color,
+	)
+}
+
+#[cfg(feature = "enable")]
+unsafe fn alloc_srcloc(line: u32, file: &[u8], function: &[u8], name: Option<&str>, color: u32) -> u64 {
+	match name {
+		Some(name) => sys::___tracy_alloc_srcloc_name(
+			line,
+			file.as_ptr() as _,
+			file.len(),
+			function.as_ptr() as _,
+			function.len(),
+			name.as_ptr() as _,
+			name.len(),
+			color,
+		),
+		None => sys::___tracy_alloc_srcloc(
+			line,
+			file.as_ptr() as _,
+			file.len(),
+			function.as_ptr() as _,
+			function.len(),
+			color,
+		),
+	}
+}
+
+/// Create a callstack sampled zone.
+#[inline(always)]
+pub fn zone_sample(loc: &'static ZoneLocation, depth: u32, active: bool) -> Zone {
+	#[cfg(feature = "enable")]
+	unsafe {
+		Zone::begun(
+			sys::___tracy_emit_zone_begin_callstack(
+				&loc.loc,
+				depth as _,
+				(active && crate::instrumentation_enabled() && loc.is_enabled()) as _,
+			),
+			StackLocation::Source(&loc.loc),
+		)
+	}
+
+	#[cfg(not(feature = "enable"))]
+	Zone {
+		unsend: PhantomData,
+		ctx: (),
+	}
+}
+
+/// Create a zone that reports an overrun if it lasts longer than the budget of its location, which is initially
+/// `budget` nanoseconds.
+#[inline(always)]
+pub fn zone_budget(loc: &'static ZoneLocation, budget: u64, active: bool) -> BudgetZone {
+	let zone = zone(loc, active);
+
+	#[cfg(feature = "enable")]
+	{
+		let start = zone.is_active().then(|| {
+			let _ = loc
+				.budget
+				.compare_exchange(BUDGET_UNSET, budget, Ordering::Relaxed, Ordering::Relaxed);
+			(loc, Instant::now())
+		});
+		BudgetZone { zone, start }
+	}
+
+	#[cfg(not(feature = "enable"))]
+	BudgetZone { zone }
+}
+
+/// Create a zone that is only emitted if it lasts at least `min` nanoseconds.
+#[inline(always)]
+pub fn zone_min(loc: &'static ZoneLocation, min: u64, active: bool) -> DeferredZone {
+	#[cfg(feature = "enable")]
+	{
+		if !(active && crate::instrumentation_enabled() && loc.is_enabled()) {
+			return DeferredZone::inactive();
+		}
+
+		let token = push_stack(StackLocation::Source(&loc.loc), None);
+		DEFERRED.with_borrow_mut(|deferred| {
+			deferred.open += 1;
+			DeferredZone {
+				unsend: PhantomData,
+				loc: Some(loc),
+				min,
+				start: deferred_time(),
+				first: deferred.records.len(),
+				token,
+			}
+		})
+	}
+
+	#[cfg(not(feature = "enable"))]
+	DeferredZone { unsend: PhantomData }
+}
+
+/// Parse a duration literal such as `100us` into nanoseconds. The units `ns`, `us`, `ms`, and `s` are supported.
+#[doc(hidden)]
+pub const fn duration_nanos(duration: &str) -> u64 {
+	let bytes = duration.as_bytes();
+	let mut value = 0u64;
+	let mut i = 0;
+	while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'_') {
+		if bytes[i] != b'_' {
+			value = value * 10 + (bytes[i] - b'0') as u64;
+		}
+		i += 1;
+	}
+	if i == 0 {
+		panic!("expected a duration such as `100us`");
+	}
+
+	let (_, unit) = bytes.split_at(i);
+	let scale = match unit {
+		b"ns" => 1,
+		b"us" => 1_000,
+		b"ms" => 1_000_000,
+		b"s" => 1_000_000_000,
+		_ => panic!("expected a duration unit of `ns`, `us`, `ms`, or `s`"),
+	};
+	value * scale
+}
+
+/// A zone. The zone ends when this object is dropped.
+pub struct Zone {
+	unsend: PhantomData<*mut ()>,
+	#[cfg(feature = "enable")]
+	ctx: sys::___tracy_c_zone_context,
+	#[cfg(not(feature = "enable"))]
+	ctx: (),
+	#[cfg(feature = "enable")]
+	token: Token,
+}
+
+impl Zone {
+	/// The maximum length of zone text and names, in bytes. Longer strings are truncated.
+	pub const MAX_TEXT_LEN: usize = u16::MAX as usize - 1;
+
+	/// A zone that is never captured.
+	#[inline(always)]
+	pub const fn inactive() -> Zone {
+		Zone {
+			unsend: PhantomData,
+			#[cfg(feature = "enable")]
+			ctx: sys::___tracy_c_zone_context { id: 0, active: 0 },
+			#[cfg(not(feature = "enable"))]
+			ctx: (),
+			#[cfg(feature = "enable")]
+			token: NO_TOKEN,
+		}
+	}
+
+	/// Wrap a zone that was just begun, pushing it onto the zone stack of the thread if it is active.
+	#[cfg(feature = "enable")]
+	#[inline(always)]
+	fn begun(ctx: sys::___tracy_c_zone_context, location: StackLocation) -> Zone {
+		let token = if ctx.active != 0 {
+			push_stack(location, Some(ctx))
+		} else {
+			NO_TOKEN
+		};
+		Zone {
+			unsend: PhantomData,
+			ctx,
+			token,
+		}
+	}
+
+	/// Begin a zone named `name` at the location of the caller.
+	///
+	/// See [`ZoneBuilder`] for more options.
+	#[inline(always)]
+	#[track_caller]
+	pub fn here(name: &str) -> Zone { ZoneBuilder::here(name).begin() }
+
+	/// Whether the zone is being captured by the profiler.
+	#[inline(always)]
+	pub fn is_active(&self) -> bool {
+		#[cfg(feature = "enable")]
+		{
+			self.ctx.active != 0
+		}
+
+		#[cfg(not(feature = "enable"))]
+		false
+	}
+
+	/// Attach text to the zone. Can be called multiple times, each call appending a new line.
+	#[inline(always)]
+	pub fn text(&self, text: &str) {
+		#[cfg(feature = "enable")]
+		unsafe {
+			sys::___tracy_emit_zone_text(self.ctx, text.as_ptr() as _, text.len().min(Self::MAX_TEXT_LEN));
+		}
+	}
+
+	/// Attach formatted text to the zone, using a reusable per-thread buffer. Prefer [`zone_text!`], which skips
+	/// formatting when the zone is inactive.
+	#[inline(always)]
+	pub fn text_fmt(&self, args: fmt::Arguments) {
+		#[cfg(feature = "enable")]
+		{
+			thread_local! {
+				static BUFFER: RefCell<String> = const { RefCell::new(String::new()) };
+			}
+
+			if let Some(text) = args.as_str() {
+				return self.text(text);
+			}
+
+			BUFFER.with(|buffer| match buffer.try_borrow_mut() {
+				Ok(mut buffer) => {
+					buffer.clear();
+					let _ = TruncatingWriter(&mut buffer).write_fmt(args);
+					self.text(&buffer);
+				},
+				// Formatting the arguments attached text to another zone on this thread.
+				Err(_) => {
+					let mut buffer = String::new();
+					let _ = TruncatingWriter(&mut buffer).write_fmt(args);
+					self.text(&buffer);
+				},
+			});
+		}
+	}
+
+	/// Override the name of the zone.
+	#[inline(always)]
+	pub fn name(&self, name: &str) {
+		#[cfg(feature = "enable")]
+		unsafe {
+			sys::___tracy_emit_zone_name(self.ctx, name.as_ptr() as _, name.len().min(Self::MAX_TEXT_LEN));
+		}
+	}
+
+	/// Override the color of the zone.
+	#[inline(always)]
+	pub fn color(&self, color: Color) {
+		#[cfg(feature = "enable")]
+		unsafe {
+			sys::___tracy_emit_zone_color(self.ctx, color.to_u32());
+		}
+	}
+
+	/// Attach a numeric value to the zone.
+	#[inline(always)]
+	pub fn value(&self, value: u64) {
+		#[cfg(feature = "enable")]
+		unsafe {
+			sys::___tracy_emit_zone_value(self.ctx, value);
+		}
+	}
+}
+
+/// A zone created by [`zone!`], so that `level =`, `every =`, `limit =`, and `perf` can be combined with every kind of
+/// zone.
+pub trait ZoneGuard {
+	/// A zone that is never captured.
+	fn inactive() -> Self;
+
+	/// Whether the zone is being captured.
+	fn is_active(&self) -> bool;
+
+	/// Attach formatted text to the zone, if the kind of zone supports text.
+	fn text_fmt(&self, args: fmt::Arguments);
+}
+
+impl ZoneGuard for Zone {
+	#[inline(always)]
+	fn inactive() -> Self { Zone::inactive() }
+
+	#[inline(always)]
+	fn is_active(&self) -> bool { Zone::is_active(self) }
+
+	#[inline(always)]
+	fn text_fmt(&self, args: fmt::Arguments) { Zone::text_fmt(self, args) }
+}
+
+#[cfg(feature = "enable")]
+impl Drop for Zone {
+	#[inline(always)]
+	fn drop(&mut self) {
+		// The zone was already ended if its thread panicked, see `crate::install_panic_hook`.
+		if self.ctx.active != 0 && pop_stack(&self.token) {
+			unsafe {
+				sys::___tracy_emit_zone_end(self.ctx);
+			}
+		}
+	}
+}
+
+/// The names of the active zones of the current thread, outermost first.
+///
+/// Zones are named by their location, so zones with a name that is not known at compile time appear with the name of
+/// the function they are in, and zones created at the location of the caller appear with their file. Without the
+/// `enable` feature, the stack is always empty.
+///
+/// # Example
+/// ```
+/// # use tracy_full::zone;
+/// zone!("frame");
+/// zone!("render");
+/// let path = zone::current_stack().join(" > "); // "frame > render"
+/// ```
+pub fn current_stack() -> Vec<&'static str> {
+	#[cfg(feature = "enable")]
+	return STACK
+		.try_with(|stack| stack.borrow().iter().map(|entry| entry.location.name()).collect())
+		.unwrap_or_default();
+
+	#[cfg(not(feature = "enable"))]
+	Vec::new()
+}
+
+/// Install a panic hook that prints the zone stack of the panicking thread, before calling the previous hook.
+pub fn install_stack_panic_hook() {
+	let previous = std::panic::take_hook();
+	std::panic::set_hook(Box::new(move |info| {
+		let stack = current_stack();
+		if !stack.is_empty() {
+			eprintln!("zone stack: {}", stack.join(" > "));
+		}
+		previous(info);
+	}));
+}
+
+/// The location of a zone on the zone stack.
+#[cfg(feature = "enable")]
+#[derive(Copy, Clone)]
+enum StackLocation {
+	Source(&'static sys::___tracy_source_location_data),
+	Caller(&'static std::panic::Location<'static>),
+}
+
+#[cfg(feature = "enable")]
+impl StackLocation {
+	/// The name of the zone, the function it is in if it has no static name, or its file if it is at the location of
+	/// the caller.
+	fn name(self) -> &'static str {
+		match self {
+			StackLocation::Source(loc) => unsafe {
+				CStr::from_ptr(if loc.name.is_null() { loc.function } else { loc.name })
+					.to_str()
+					.unwrap_or("?")
+			},
+			StackLocation::Caller(loc) => loc.file(),
+		}
+	}
+}
+
+#[cfg(feature = "enable")]
+impl fmt::Display for StackLocation {
+	/// Formats the name of the zone along with its file and line.
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			StackLocation::Source(loc) => unsafe {
+				let file = CStr::from_ptr(loc.file).to_string_lossy();
+				write!(f, "`{}` at {}:{}", self.name(), file, loc.line)
+			},
+			StackLocation::Caller(loc) => write!(f, "zone at {}", loc),
+		}
+	}
+}
+
+#[cfg(feature = "enable")]
+struct StackEntry {
+	location: StackLocation,
+	/// The context of the zone, if it has not been ended. Deferred zones have none.
+	ctx: Option<sys::___tracy_c_zone_context>,
+	fiber: *const std::ffi::c_char,
+	#[cfg(feature = "validate")]
+	id: u64,
+}
+
+/// Identifies a zone on the zone stack, to validate that zones end in the right order.
+#[cfg(all(feature = "enable", feature = "validate"))]
+type Token = Option<(u64, StackLocation)>;
+#[cfg(all(feature = "enable", not(feature = "validate")))]
+#[derive(Copy, Clone)]
+struct Token;
+
+#[cfg(all(feature = "enable", feature = "validate"))]
+const NO_TOKEN: Token = None;
+#[cfg(all(feature = "enable", not(feature = "validate")))]
+const NO_TOKEN: Token = Token;
+
+#[cfg(feature = "enable")]
+thread_local! {
+	static STACK: RefCell<Vec<StackEntry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Push a zone onto the zone stack of the current thread.
+#[cfg(feature = "enable")]
+#[inline(always)]
+fn push_stack(location: StackLocation, ctx: Option<sys::___tracy_c_zone_context>) -> Token {
+	#[cfg(feature = "validate")]
+	let id = crate::validate::next_id();
+	let _ = STACK.try_with(|stack| {
+		stack.borrow_mut().push(StackEntry {
+			location,
+			ctx,
+			fiber: crate::current_fiber(),
+			#[cfg(feature = "validate")]
+			id,
+		})
+	});
+
+	#[cfg(feature = "validate")]
+	return Some((id, location));
+	#[cfg(not(feature = "validate"))]
+	Token
+}
+
+/// Pop a zone from the zone stack of the current thread, validating that it is the innermost zone. Returns false if
+/// the zone was already ended by [`end_stack`].
+#[cfg(feature = "enable")]
+#[inline(always)]
+fn pop_stack(token: &Token) -> bool {
+	#[cfg(not(feature = "validate"))]
+	return STACK
+		.try_with(|stack| stack.borrow_mut().pop().is_none_or(|entry| entry.ctx.is_some()))
+		.unwrap_or(true);
+
+	#[cfg(feature = "validate")]
+	return {
+		let Some((id, location)) = *token else {
+			return true;
+		};
+		let mut open = true;
+		let error = STACK
+			.try_with(|stack| {
+				let mut stack = stack.borrow_mut();
+				let Some(index) = stack.iter().rposition(|entry| entry.id == id) else {
+					return Some(format!("{} ended on a thread where it is not active", location));
+				};
+				// Zones of other fibers can interleave with this one, but zones of its own fiber must have ended.
+				let entry = stack.remove(index);
+				open = entry.ctx.is_some();
+				let mut inner = None;
+				let mut i = index;
+				while i < stack.len() {
+					if stack[i].fiber == entry.fiber {
+						// Drop the zones that were left behind, so that the error is only reported once.
+						inner.get_or_insert(stack.remove(i).location);
+					} else {
+						i += 1;
+					}
+				}
+				if let Some(inner) = inner {
+					return Some(format!(
+						"{} ended before {}, which began inside it: zones must end in reverse order, and must not be \
+						 swapped or forgotten",
+						location, inner
+					));
+				}
+				let fiber = crate::current_fiber();
+				(entry.fiber != fiber).then(|| {
+					format!(
+						"{} began in {} but ended in {}",
+						location,
+						crate::validate::fiber_name(entry.fiber),
+						crate::validate::fiber_name(fiber)
+					)
+				})
+			})
+			.ok()
+			.flatten();
+		if let Some(error) = error {
+			crate::validate::violation(error);
+		}
+		open
+	};
+}
+
+/// End the zones on the zone stack of the current thread, innermost first, for when the thread is about to die. The
+/// zones are kept on the stack, but are not ended again when they are dropped.
+#[cfg(feature = "enable")]
+pub(crate) fn end_stack() {
+	let _ = STACK.try_with(|stack| {
+		let Ok(mut stack) = stack.try_borrow_mut() else {
+			return;
+		};
+		let current = crate::current_fiber();
+		let mut fiber = current;
+		for entry in stack.iter_mut().rev() {
+			let Some(ctx) = entry.ctx.take() else {
+				continue;
+			};
+			// Zones are ended on the fiber they began in.
+			#[cfg(feature = "futures")]
+			if entry.fiber != fiber {
+				unsafe { crate::fiber_switch(entry.fiber) };
+				fiber = entry.fiber;
+			}
+			unsafe {
+				sys::___tracy_emit_zone_end(ctx);
+			}
+		}
+		#[cfg(feature = "futures")]
+		if fiber != current {
+			unsafe { crate::fiber_switch(current) };
+		}
+	});
+}
+
+/// A zone with a time budget. See `zone!(budget = ...)`.
+///
+/// If the zone lasts longer than the budget of its location, a message is emitted when it ends, and the overrun count
+/// of the location is incremented and plotted as "<name> budget overruns". Budgets can be changed at runtime with
+/// [`ZoneLocation::set_budget`], for example from [`crate::callsite::for_each_callsite`].
+pub struct BudgetZone {
+	zone: Zone,
+	#[cfg(feature = "enable")]
+	start: Option<(&'static ZoneLocation, Instant)>,
+}
+
+impl Deref for BudgetZone {
+	type Target = Zone;
+
+	fn deref(&self) -> &Zone { &self.zone }
+}
+
+impl ZoneGuard for BudgetZone {
+	#[inline(always)]
+	fn inactive() -> Self {
+		BudgetZone {
+			zone: Zone::inactive(),
+			#[cfg(feature = "enable")]
+			start: None,
+		}
+	}
+
+	#[inline(always)]
+	fn is_active(&self) -> bool { self.zone.is_active() }
+
+	#[inline(always)]
+	fn text_fmt(&self, args: fmt::Arguments) { self.zone.text_fmt(args) }
+}
+
+#[cfg(feature = "enable")]
+impl Drop for BudgetZone {
+	#[inline(always)]
+	fn drop(&mut self) {
+		if let Some((loc, start)) = self.start {
+			let elapsed = start.elapsed();
+			let budget = loc.budget.load(Ordering::Relaxed);
+			if budget != BUDGET_NONE && elapsed.as_nanos() > budget as u128 {
+				budget_overrun(loc, elapsed, Duration::from_nanos(budget));
+			}
+		}
+	}
+}
+
+#[cfg(feature = "enable")]
+const BUDGET_UNSET: u64 = u64::MAX;
+#[cfg(feature = "enable")]
+const BUDGET_NONE: u64 = u64::MAX - 1;
+
+#[cold]
+#[cfg(feature = "enable")]
+fn budget_overrun(loc: &'static ZoneLocation, elapsed: Duration, budget: Duration) {
+	let overruns = loc.overruns.fetch_add(1, Ordering::Relaxed) + 1;
+	let name = loc.name().unwrap_or_else(|| loc.function());
+	let message = format!("{} took {:?}, over its budget of {:?}", name, elapsed, budget);
+	let plot = loc
+		.overrun_plot
+		.get_or_init(|| CString::new(format!("{} budget overruns", name)).unwrap_or_default());
+	crate::message::message(
+		&message,
+		Some(Color::YELLOW),
+		loc.budget_callstack.load(Ordering::Relaxed),
+	);
+	unsafe {
+		sys::___tracy_emit_plot(plot.as_ptr(), overruns as f64);
+	}
+}
+
+/// A zone that is only emitted if it lasts at least a minimum duration. See `zone!(min = ...)`.
+///
+/// Tracy cannot begin a CPU zone in the past, so deferred zones are recorded when they end, and appear on a separate
+/// timeline named "Deferred zones". Deferred zones nested in another deferred zone are emitted when the outermost one
+/// ends: if a parent is too short to be emitted, its children are promoted to the parent's place. Regular zones inside
+/// a deferred zone are emitted as usual, on the thread's own timeline.
+pub struct DeferredZone {
+	unsend: PhantomData<*mut ()>,
+	#[cfg(feature = "enable")]
+	loc: Option<&'static ZoneLocation>,
+	#[cfg(feature = "enable")]
+	min: u64,
+	#[cfg(feature = "enable")]
+	start: i64,
+	#[cfg(feature = "enable")]
+	first: usize,
+	#[cfg(feature = "enable")]
+	token: Token,
+}
+
+impl DeferredZone {
+	/// A deferred zone that is never captured.
+	#[inline(always)]
+	pub const fn inactive() -> DeferredZone {
+		DeferredZone {
+			unsend: PhantomData,
+			#[cfg(feature = "enable")]
+			loc: None,
+			#[cfg(feature = "enable")]
+			min: 0,
+			#[cfg(feature = "enable")]
+			start: 0,
+			#[cfg(feature = "enable")]
+			first: 0,
+			#[cfg(feature = "enable")]
+			token: NO_TOKEN,
+		}
+	}
+
+	/// Whether the zone is being recorded. It is only emitted if it also lasts long enough.
+	#[inline(always)]
+	pub fn is_active(&self) -> bool {
+		#[cfg(feature = "enable")]
+		return self.loc.is_some();
+
+		#[cfg(not(feature = "enable"))]
+		false
+	}
+}
+
+impl ZoneGuard for DeferredZone {
+	#[inline(always)]
+	fn inactive() -> Self { DeferredZone::inactive() }
+
+	#[inline(always)]
+	fn is_active(&self) -> bool { DeferredZone::is_active(self) }
+
+	/// Deferred zones are emitted as GPU zones, which have no text.
+	#[inline(always)]
+	fn text_fmt(&self, args: fmt::Arguments) {}
+}
+
+#[cfg(feature = "enable")]
+impl Drop for DeferredZone {
+	#[inline(always)]
+	fn drop(&mut self) {
+		if let Some(loc) = self.loc {
+			pop_stack(&self.token);
+			let end = deferred_time();
+			DEFERRED.with_borrow_mut(|deferred| {
+				if (end - self.start) as u64 >= self.min {
+					let record = DeferredRecord {
+						loc,
+						start: self.start,
+						end,
+					};
+					deferred.records.insert(self.first.min(deferred.records.len()), record);
+				}
+				deferred.open -= 1;
+				if deferred.open == 0 {
+					emit_deferred(&deferred.records);
+					deferred.records.clear();
+				}
+			});
+		}
+	}
+}
+
+#[cfg(feature = "enable")]
+struct Deferred {
+	open: usize,
+	records: Vec<DeferredRecord>,
+}
+
+#[cfg(feature = "enable")]
+struct DeferredRecord {
+	loc: &'static ZoneLocation,
+	start: i64,
+	end: i64,
+}
+
+#[cfg(feature = "enable")]
+thread_local! {
+	static DEFERRED: RefCell<Deferred> = const {
+		RefCell::new(Deferred {
+			open: 0,
+			records: Vec::new(),
+		})
+	};
+}
+
+/// The timestamp of deferred zones, in nanoseconds.
+#[cfg(feature = "enable")]
+fn deferred_time() -> i64 {
+	static EPOCH: OnceLock<Instant> = OnceLock::new();
+	EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as i64
+}
+
+/// Emit records, ordered by their start, as zones of the deferred zone GPU context.
+#[cfg(feature = "enable")]
+fn emit_deferred(records: &[DeferredRecord]) {
+	static CONTEXT: OnceLock<u8> = OnceLock::new();
+	static QUERY_ID: AtomicU16 = AtomicU16::new(0);
+
+	if records.is_empty() {
+		return;
+	}
+
+	let context = *CONTEXT.get_or_init(|| {
+		let context = crate::next_gpu_context();
+		let name = "Deferred zones";
+		unsafe {
+			sys::___tracy_emit_gpu_new_context_serial(sys::___tracy_gpu_new_context_data {
+				gpuTime: deferred_time(),
+				period: 1.0,
+				context,
+				flags: 0,
+				type_: 0,
+			});
+			sys::___tracy_emit_gpu_context_name_serial(sys::___tracy_gpu_context_name_data {
+				context,
+				name: name.as_ptr() as _,
+				len: name.len() as _,
+			});
+		}
+		context
+	});
+
+	let time = |gpu_time, query_id| unsafe {
+		sys::___tracy_emit_gpu_time_serial(sys::___tracy_gpu_time_data {
+			gpuTime: gpu_time,
+			queryId: query_id,
+			context,
+		});
+	};
+	let end = |gpu_time| unsafe {
+		let query_id = QUERY_ID.fetch_add(1, Ordering::Relaxed);
+		sys::___tracy_emit_gpu_zone_end_serial(sys::___tracy_gpu_zone_end_data {
+			queryId: query_id,
+			context,
+		});
+		time(gpu_time, query_id);
+	};
+
+	let mut open: Vec<i64> = Vec::new();
+	for record in records {
+		while let Some(&last) = open.last().filter(|&&last| last <= record.start) {
+			open.pop();
+			end(last);
+		}
+
+		let query_id = QUERY_ID.fetch_add(1, Ordering::Relaxed);
+		unsafe {
+			sys::___tracy_emit_gpu_zone_begin_serial(sys::___tracy_gpu_zone_begin_data {
+				srcloc: &record.loc.loc as *const _ as u64,
+				queryId: query_id,
+				context,
+			});
+		}
+		time(record.start, query_id);
+		open.push(record.end);
+	}
+	while let Some(last) = open.pop() {
+		end(last);
+	}
+}
+
+#[doc(hidden)]
+pub struct Fields<'a>(pub &'a [(&'static str, &'a dyn fmt::Display)]);
+
+impl Fields<'_> {
+	#[inline(always)]
+	pub fn attach(&self, zone: &Zone) {
+		if !self.0.is_empty() {
+			zone.text_fmt(format_args!("{}", self));
+		}
+	}
+}
+
+impl fmt::Display for Fields<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (i, (key, value)) in self.0.iter().enumerate() {
+			if i != 0 {
+				f.write_str(" ")?;
+			}
+			write!(f, "{}={}", key, value)?;
+		}
+		Ok(())
+	}
+}
+
+#[doc(hidden)]
+pub struct DebugField<'a, T: ?Sized>(pub &'a T);
+
+impl<T: fmt::Debug + ?Sized> fmt::Display for DebugField<'_, T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::Debug::fmt(self.0, f) }
+}
+
+/// Writes to a string, stopping at [`Zone::MAX_TEXT_LEN`] bytes.
+struct TruncatingWriter<'a>(&'a mut String);
+
+impl Write for TruncatingWriter<'_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		let truncated = crate::truncate(s, Zone::MAX_TEXT_LEN - self.0.len());
+		self.0.push_str(truncated);
+		if truncated.len() == s.len() {
+			Ok(())
+		} else {
+			Err(fmt::Error)
+		}
+	}
+}
+
+/// A builder for zones, for when the [`zone!`] macro is not flexible enough.
+///
+/// # Example
+/// ```
+/// # use tracy_full::{color::Color, get_location, zone::ZoneBuilder};
+/// let zone = ZoneBuilder::new(get_location!())
+/// 	.name("Load")
+/// 	.color(Color::BLUE)
+/// 	.callstack(8)
+/// 	.active(true)
+/// 	.begin();
+/// ```
+pub struct ZoneBuilder<'a> {
+	location: BuilderLocation,
+	name: Option<&'a str>,
+	color: Option<Color>,
+	depth: u32,
+	active: bool,
+}
+
+enum BuilderLocation {
+	Static(&'static ZoneLocation),
+	Caller(&'static std::panic::Location<'static>),
+}
 
-	($name:literal, $enabled:expr $(,)?) => {
-		let loc = $crate::get_location!($name);
-		let _zone = $crate::zone::zone(loc, $enabled);
-	};
+impl<'a> ZoneBuilder<'a> {
+	/// Build a zone at a static location, usually created with [`get_location!`].
+	#[inline(always)]
+	pub const fn new(loc: &'static ZoneLocation) -> Self {
+		Self {
+			location: BuilderLocation::Static(loc),
+			name: None,
+			color: None,
+			depth: 0,
+			active: true,
+		}
+	}
 
-	($color:expr, $enabled:expr $(,)?) => {
-		let loc = $crate::get_location!($color);
-		let _zone = $crate::zone::zone(loc, $enabled);
-	};
+	/// Build a zone named `name` at the location of the caller.
+	#[inline(always)]
+	#[track_caller]
+	pub fn here(name: &'a str) -> Self { Self::at(std::panic::Location::caller(), name) }
 
-	($name:literal, $color:expr, $enabled:expr $(,)?) => {
-		let loc = $crate::get_location!($name, $color);
-		let _zone = $crate::zone::zone(loc, $enabled);
-	};
-}
+	#[inline(always)]
+	const fn at(location: &'static std::panic::Location<'static>, name: &'a str) -> Self {
+		Self {
+			location: BuilderLocation::Caller(location),
+			name: Some(name),
+			color: None,
+			depth: 0,
+			active: true,
+		}
+	}
 
-/// Mark a zone in the current scope, sampling the callstack.
-#[macro_export]
-macro_rules! zone_sample {
-	($depth:literal) => {
-		let _zone = $crate::zone::zone_sample($crate::get_location!(), $crate::clamp_callstack_depth($depth), true);
-	};
+	/// Set the name of the zone.
+	#[inline(always)]
+	pub const fn name(mut self, name: &'a str) -> Self {
+		self.name = Some(name);
+		self
+	}
 
-	($name:literal, $depth:literal, $enabled:expr $(,)?) => {
-		let _zone = $crate::zone::zone_sample(
-			$crate::get_location!($name),
-			$crate::clamp_callstack_depth($depth),
-			$enabled,
-		);
-	};
+	/// Set the color of the zone.
+	#[inline(always)]
+	pub const fn color(mut self, color: Color) -> Self {
+		self.color = Some(color);
+		self
+	}
 
-	($color:expr, $depth:literal, $enabled:expr $(,)?) => {
-		let _zone = $crate::zone::zone_sample(
-			$crate::get_location!($color),
-			$crate::clamp_callstack_depth($depth),
-			$enabled,
-		);
-	};
+	/// Sample the callstack with the given depth when the zone begins. A depth of 0 disables sampling.
+	#[inline(always)]
+	pub const fn callstack(mut self, depth: u32) -> Self {
+		self.depth = crate::clamp_callstack_depth(depth);
+		self
+	}
 
-	($name:literal, $color:expr, $depth:literal, $enabled:expr $(,)?) => {
-		let _zone = $crate::zone::zone_sample(
-			$crate::get_location!($name, $color),
-			$crate::clamp_callstack_depth($depth),
-			$enabled,
-		);
-	};
-}
+	/// Set whether the zone is active.
+	#[inline(always)]
+	pub const fn active(mut self, active: bool) -> Self {
+		self.active = active;
+		self
+	}
 
-/// Create a zone.
-#[inline(always)]
-pub fn zone(loc: &'static ZoneLocation, active: bool) -> Zone {
-	#[cfg(feature = "enable")]
-	unsafe {
+	/// Begin the zone.
+	///
+	/// Zones with a static location and no name or color override use the static source location. All others allocate
+	/// a source location, which is more expensive.
+	#[inline(always)]
+	pub fn begin(self) -> Zone {
+		#[cfg(feature = "enable")]
+		unsafe {
+			let enabled = match self.location {
+				BuilderLocation::Static(loc) => loc.is_enabled(),
+				BuilderLocation::Caller(_) => true,
+			};
+			if !(self.active && enabled && crate::instrumentation_enabled()) {
+				return Zone::inactive();
+			}
+
+			let stack_name = match &self.location {
+				BuilderLocation::Static(loc) => StackLocation::Source(&loc.loc),
+				BuilderLocation::Caller(loc) => StackLocation::Caller(loc),
+			};
+			let ctx = match (self.location, self.name, self.color) {
+				(BuilderLocation::Static(loc), None, None) => {
+					if self.depth > 0 {
+						sys::___tracy_emit_zone_begin_callstack(&loc.loc, self.depth as _, 1)
+					} else {
+						sys::___tracy_emit_zone_begin(&loc.loc, 1)
+					}
+				},
+				(location, name, color) => {
+					let srcloc = match location {
+						BuilderLocation::Static(loc) => {
+							alloc_location(loc, name, color.map(|c| c.to_u32()).unwrap_or(loc.loc.color))
+						},
+						BuilderLocation::Caller(loc) => alloc_srcloc(
+							loc.line(),
+							loc.file().as_bytes(),
+							&[],
+							name,
+							color.map(|c| c.to_u32()).unwrap_or(0),
+						),
+					};
+					if self.depth > 0 {
+						sys::___tracy_emit_zone_begin_alloc_callstack(srcloc, self.depth as _, 1)
+					} else {
+						sys::___tracy_emit_zone_begin_alloc(srcloc, 1)
+					}
+				},
+			};
+
+			Zone::begun(ctx, stack_name)
+		}
+
+		#[cfg(not(feature = "enable"))]
 		Zone {
 			unsend: PhantomData,
-			ctx: sys::___tracy_emit_zone_begin(&loc.loc, active as _),
+			ctx: (),
 		}
 	}
+}
 
-	#[cfg(not(feature = "enable"))]
-	Zone {
-		unsend: PhantomData,
-		ctx: (),
-	}
+/// Run `f` inside a zone named `name`, returning its result.
+///
+/// # Example
+/// ```
+/// # use tracy_full::zone;
+/// let sum = zone::scope("Sum", || (0..100).sum::<u32>());
+/// ```
+///
+/// Like [`zone_named`], the source location for each distinct name is cached for the rest of the program.
+#[inline(always)]
+#[track_caller]
+pub fn scope<R>(name: &str, f: impl FnOnce() -> R) -> R {
+	let _zone = zone_at(std::panic::Location::caller(), name);
+	f()
 }
 
-/// Create a callstack sampled zone.
+/// Create a zone named `name` at the location of a caller, caching the source location like [`zone_named`].
 #[inline(always)]
-#[cfg(feature = "enable")]
-pub fn zone_sample(loc: &'static ZoneLocation, depth: u32, active: bool) -> Zone {
+fn zone_at(location: &'static std::panic::Location<'static>, name: &str) -> Zone {
 	#[cfg(feature = "enable")]
 	unsafe {
-		Zone {
-			unsend: PhantomData,
-			ctx: sys::___tracy_emit_zone_begin_callstack(&loc.loc, depth as _, active as _),
+		if !crate::instrumentation_enabled() {
+			return Zone::inactive();
 		}
+
+		let interned = intern_caller(location, name);
+		Zone::begun(
+			sys::___tracy_emit_zone_begin(&interned.0, 1),
+			StackLocation::Source(&interned.0),
+		)
 	}
 
 	#[cfg(not(feature = "enable"))]
@@ -108,42 +1814,171 @@ pub fn zone_sample(loc: &'static ZoneLocation, depth: u32, active: bool) -> Zone
 	}
 }
 
-pub struct Zone {
-	unsend: PhantomData<*mut ()>,
-	#[cfg(feature = "enable")]
-	ctx: sys::___tracy_c_zone_context,
-	#[cfg(not(feature = "enable"))]
-	ctx: (),
+/// Extension trait for profiling iterators.
+///
+/// # Example
+/// ```
+/// # use tracy_full::zone::ZoneIterator;
+/// // One zone around the whole iteration.
+/// let sum: u32 = (0..100).zoned("Sum").sum();
+///
+/// // One zone per item.
+/// for i in (0..100).zone_each("Item") {}
+/// ```
+pub trait ZoneIterator: Iterator + Sized {
+	/// Mark a zone named `name` from the first call to `next` until the iterator is exhausted or dropped.
+	///
+	/// The value of the zone is the number of items produced. The zone stays open while the iterator is alive, so like
+	/// any other zone, zoned iterators must end in the reverse order that they began: zipping two zoned iterators ends
+	/// the first zone while the second is still open, which Tracy cannot display and `validate` reports. Use
+	/// [`zone_each`](ZoneIterator::zone_each) or a zone around the whole loop for iterators that are advanced together.
+	///
+	/// Like [`zone_named`], the source location for each distinct name is cached for the rest of the program.
+	#[track_caller]
+	fn zoned(self, name: &str) -> Zoned<'_, Self> {
+		Zoned {
+			inner: self,
+			name,
+			location: std::panic::Location::caller(),
+			zone: None,
+			count: 0,
+			finished: false,
+		}
+	}
+
+	/// Mark a zone named `name` around every call to `next`.
+	///
+	/// The value of each zone is the index of the item produced. No zone is emitted for the final call, if the size
+	/// hint of the iterator shows that no items are left. The source location is cached like for
+	/// [`zoned`](ZoneIterator::zoned), so zones are cheap to begin for each item.
+	#[track_caller]
+	fn zone_each(self, name: &str) -> ZoneEach<'_, Self> {
+		ZoneEach {
+			inner: self,
+			name,
+			location: std::panic::Location::caller(),
+			count: 0,
+		}
+	}
 }
 
-#[cfg(feature = "enable")]
-impl Drop for Zone {
+impl<I: Iterator> ZoneIterator for I {}
+
+/// An iterator with a zone around the whole iteration. See [`ZoneIterator::zoned`].
+pub struct Zoned<'a, I> {
+	inner: I,
+	name: &'a str,
+	location: &'static std::panic::Location<'static>,
+	zone: Option<Zone>,
+	count: u64,
+	finished: bool,
+}
+
+impl<I: Iterator> Iterator for Zoned<'_, I> {
+	type Item = I::Item;
+
 	#[inline(always)]
-	fn drop(&mut self) {
-		unsafe {
-			sys::___tracy_emit_zone_end(self.ctx);
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.finished {
+			return None;
+		}
+
+		if self.zone.is_none() {
+			self.zone = Some(zone_at(self.location, self.name));
+		}
+
+		let item = self.inner.next();
+		match item {
+			Some(_) => self.count += 1,
+			None => {
+				self.finished = true;
+				self.end();
+			},
+		}
+		item
+	}
+
+	#[inline(always)]
+	fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+impl<I> Zoned<'_, I> {
+	#[inline(always)]
+	fn end(&mut self) {
+		if let Some(zone) = self.zone.take() {
+			zone.value(self.count);
 		}
 	}
 }
 
+impl<I> Drop for Zoned<'_, I> {
+	#[inline(always)]
+	fn drop(&mut self) { self.end(); }
+}
+
+/// An iterator with a zone around every item. See [`ZoneIterator::zone_each`].
+pub struct ZoneEach<'a, I> {
+	inner: I,
+	name: &'a str,
+	location: &'static std::panic::Location<'static>,
+	count: u64,
+}
+
+impl<I: Iterator> Iterator for ZoneEach<'_, I> {
+	type Item = I::Item;
+
+	#[inline(always)]
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.inner.size_hint().1 == Some(0) {
+			return self.inner.next();
+		}
+
+		let zone = zone_at(self.location, self.name);
+		let item = self.inner.next();
+		if item.is_some() {
+			zone.value(self.count);
+			self.count += 1;
+		}
+		item
+	}
+
+	#[inline(always)]
+	fn size_hint(&self) -> (usize, Option<usize>) { self.inner.size_hint() }
+}
+
+#[doc(hidden)]
+#[cfg(feature = "unstable")]
+pub const fn type_name_of<T>() -> &'static str { std::any::type_name::<T>() }
+
+/// Copy the first `N - 1` bytes of the type name of a local type, and null-terminate them.
 #[doc(hidden)]
 #[cfg(feature = "unstable")]
-pub const fn get_function_name_from_local_type<T, const TY: usize>() -> [u8; std::any::type_name::<T>().len() - (TY + 1)]
-where
-	[(); std::any::type_name::<T>().len() - (TY + 1)]:,
-{
-	let mut name = [0; std::any::type_name::<T>().len() - (TY + 1)];
+pub const fn get_function_name_from_local_type<const N: usize>(type_name: &str) -> [u8; N] {
+	let mut name = [0; N];
 	unsafe {
-		std::ptr::copy_nonoverlapping(std::any::type_name::<T>().as_ptr(), name.as_mut_ptr(), name.len() - 1);
+		std::ptr::copy_nonoverlapping(type_name.as_ptr(), name.as_mut_ptr(), N - 1);
 		name
 	}
 }
 
+/// A static source location for zones, registered as a callsite the first time it is used.
 pub struct ZoneLocation {
 	#[cfg(feature = "enable")]
 	loc: sys::___tracy_source_location_data,
-	#[cfg(all(feature = "enable", not(feature = "unstable")))]
-	function: CString,
+	#[cfg(feature = "enable")]
+	module_path: &'static str,
+	#[cfg(feature = "enable")]
+	pub(crate) state: AtomicU8,
+	#[cfg(feature = "enable")]
+	pub(crate) next: AtomicPtr<ZoneLocation>,
+	#[cfg(feature = "enable")]
+	budget: AtomicU64,
+	#[cfg(feature = "enable")]
+	budget_callstack: AtomicU32,
+	#[cfg(feature = "enable")]
+	overruns: AtomicU64,
+	#[cfg(feature = "enable")]
+	overrun_plot: OnceLock<CString>,
 	#[cfg(not(feature = "enable"))]
 	pub loc: (),
 }
@@ -151,122 +1986,171 @@ pub struct ZoneLocation {
 unsafe impl Send for ZoneLocation {}
 unsafe impl Sync for ZoneLocation {}
 
-#[cfg(all(feature = "enable", feature = "unstable"))]
+#[cfg(feature = "enable")]
 impl ZoneLocation {
 	pub const fn from_function_file_line(function: &'static CStr, file: &'static CStr, line: u32) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: std::ptr::null(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: Color::none().to_u32(),
-			},
-		}
+		Self::new(std::ptr::null(), function, file, line, Color::none())
 	}
 
 	pub const fn from_name_function_file_line(
 		name: &'static CStr, function: &'static CStr, file: &'static CStr, line: u32,
 	) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: name.as_ptr(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: Color::none().to_u32(),
-			},
-		}
+		Self::new(name.as_ptr(), function, file, line, Color::none())
 	}
 
 	pub const fn from_function_file_line_color(
 		function: &'static CStr, file: &'static CStr, line: u32, color: Color,
 	) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: std::ptr::null(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: color.to_u32(),
-			},
-		}
+		Self::new(std::ptr::null(), function, file, line, color)
 	}
 
 	pub const fn from_name_function_file_line_color(
 		name: &'static CStr, function: &'static CStr, file: &'static CStr, line: u32, color: Color,
+	) -> Self {
+		Self::new(name.as_ptr(), function, file, line, color)
+	}
+
+	/// Set the module path of the location, used for filtering. See [`crate::callsite`].
+	pub const fn with_module_path(mut self, module_path: &'static str) -> Self {
+		self.module_path = module_path;
+		self
+	}
+
+	const fn new(
+		name: *const std::ffi::c_char, function: &'static CStr, file: &'static CStr, line: u32, color: Color,
 	) -> Self {
 		Self {
 			loc: sys::___tracy_source_location_data {
-				name: name.as_ptr(),
+				name,
 				function: function.as_ptr(),
 				file: file.as_ptr(),
 				line,
 				color: color.to_u32(),
 			},
+			module_path: "",
+			state: AtomicU8::new(callsite::UNREGISTERED),
+			next: AtomicPtr::new(std::ptr::null_mut()),
+			budget: AtomicU64::new(BUDGET_UNSET),
+			budget_callstack: AtomicU32::new(0),
+			overruns: AtomicU64::new(0),
+			overrun_plot: OnceLock::new(),
 		}
 	}
-}
 
-#[cfg(all(feature = "enable", not(feature = "unstable")))]
-impl ZoneLocation {
-	pub fn from_function_file_line(function: CString, file: &'static CStr, line: u32) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: std::ptr::null(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: Color::none().to_u32(),
-			},
-			function,
+	/// The module path of the location.
+	pub fn module_path(&self) -> &'static str { self.module_path }
+
+	/// The name of the zone, if it has one.
+	pub fn name(&self) -> Option<&'static str> {
+		if self.loc.name.is_null() {
+			None
+		} else {
+			unsafe { CStr::from_ptr(self.loc.name).to_str().ok() }
 		}
 	}
 
-	pub fn from_name_function_file_line(
-		name: &'static CStr, function: CString, file: &'static CStr, line: u32,
-	) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: name.as_ptr(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: Color::none().to_u32(),
-			},
-			function,
+	/// The function the location is in. Without the `unstable` feature, this is the module path.
+	pub fn function(&self) -> &'static str { unsafe { CStr::from_ptr(self.loc.function).to_str().unwrap_or("") } }
+
+	/// The file the location is in.
+	pub fn file(&self) -> &'static str { unsafe { CStr::from_ptr(self.loc.file).to_str().unwrap_or("") } }
+
+	/// The line the location is at.
+	pub fn line(&self) -> u32 { self.loc.line }
+
+	/// Whether zones at this location are enabled by the filter, registering it if required.
+	#[inline(always)]
+	pub fn is_enabled(&'static self) -> bool {
+		match self.state.load(Ordering::Relaxed) {
+			callsite::ENABLED => true,
+			callsite::DISABLED => false,
+			_ => callsite::register(self),
 		}
 	}
 
-	pub fn from_function_file_line_color(function: CString, file: &'static CStr, line: u32, color: Color) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: std::ptr::null(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: color.to_u32(),
-			},
-			function,
+	/// The time budget of zones at this location. See [`BudgetZone`].
+	pub fn budget(&self) -> Option<Duration> {
+		match self.budget.load(Ordering::Relaxed) {
+			BUDGET_UNSET | BUDGET_NONE => None,
+			budget => Some(Duration::from_nanos(budget)),
 		}
 	}
 
-	pub fn from_name_function_file_line_color(
-		name: &'static CStr, function: CString, file: &'static CStr, line: u32, color: Color,
-	) -> Self {
-		Self {
-			loc: sys::___tracy_source_location_data {
-				name: name.as_ptr(),
-				function: function.as_ptr(),
-				file: file.as_ptr(),
-				line,
-				color: color.to_u32(),
-			},
-			function,
-		}
+	/// Set the time budget of zones at this location, replacing the budget given to `zone!`. `None` disables the
+	/// budget.
+	pub fn set_budget(&self, budget: Option<Duration>) {
+		let budget = budget.map_or(BUDGET_NONE, |budget| (budget.as_nanos() as u64).min(BUDGET_NONE - 1));
+		self.budget.store(budget, Ordering::Relaxed);
+	}
+
+	/// Capture a callstack of `depth` frames with the message emitted when a zone at this location exceeds its budget.
+	/// No callstack is captured by default.
+	pub fn set_budget_callstack(&self, depth: u32) {
+		self.budget_callstack
+			.store(crate::clamp_callstack_depth(depth), Ordering::Relaxed);
+	}
+
+	/// The number of times zones at this location exceeded their budget.
+	pub fn overruns(&self) -> u64 { self.overruns.load(Ordering::Relaxed) }
+
+	/// Enable or disable zones at this location, until the filter is next changed.
+	#[inline(always)]
+	pub fn set_enabled(&'static self, enabled: bool) {
+		self.is_enabled();
+		self.state.store(
+			if enabled { callsite::ENABLED } else { callsite::DISABLED },
+			Ordering::Relaxed,
+		);
 	}
 }
 
+#[cfg(not(feature = "enable"))]
+impl ZoneLocation {
+	/// The module path of the location.
+	#[inline(always)]
+	pub fn module_path(&self) -> &'static str { "" }
+
+	/// The name of the zone, if it has one.
+	#[inline(always)]
+	pub fn name(&self) -> Option<&'static str> { None }
+
+	/// The function the location is in.
+	#[inline(always)]
+	pub fn function(&self) -> &'static str { "" }
+
+	/// The file the location is in.
+	#[inline(always)]
+	pub fn file(&self) -> &'static str { "" }
+
+	/// The line the location is at.
+	#[inline(always)]
+	pub fn line(&self) -> u32 { 0 }
+
+	/// Whether zones at this location are enabled by the filter.
+	#[inline(always)]
+	pub fn is_enabled(&'static self) -> bool { true }
+
+	/// Enable or disable zones at this location, until the filter is next changed.
+	#[inline(always)]
+	pub fn set_enabled(&'static self, enabled: bool) {}
+
+	/// The time budget of zones at this location.
+	#[inline(always)]
+	pub fn budget(&self) -> Option<Duration> { None }
+
+	/// Set the time budget of zones at this location.
+	#[inline(always)]
+	pub fn set_budget(&self, budget: Option<Duration>) {}
+
+	/// Capture a callstack of `depth` frames with the message emitted when a zone at this location exceeds its budget.
+	#[inline(always)]
+	pub fn set_budget_callstack(&self, depth: u32) {}
+
+	/// The number of times zones at this location exceeded their budget.
+	#[inline(always)]
+	pub fn overruns(&self) -> u64 { 0 }
+}
+
 /// Get a zone location.
 #[cfg(all(feature = "enable", feature = "unstable"))]
 #[macro_export]
@@ -274,124 +2158,110 @@ macro_rules! get_location {
 	() => {{
 		{
 			struct S;
-			static FUNCTION: &[u8] = &$crate::zone::get_function_name_from_local_type::<S, 1>();
+			const TYPE_NAME: &str = $crate::zone::type_name_of::<S>();
+			static FUNCTION: [u8; TYPE_NAME.len() - 2] = $crate::zone::get_function_name_from_local_type(TYPE_NAME);
 			static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_function_file_line(
-				unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(FUNCTION) },
+				unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(&FUNCTION) },
 				unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
 				line!(),
-			);
+			)
+			.with_module_path(module_path!());
 			&LOC
 		}
 	}};
 
 	($name:literal $(,)?) => {{
 		struct S;
-		static FUNCTION: &[u8] = &$crate::zone::get_function_name_from_local_type::<S, 1>();
+		const TYPE_NAME: &str = $crate::zone::type_name_of::<S>();
+		static FUNCTION: [u8; TYPE_NAME.len() - 2] = $crate::zone::get_function_name_from_local_type(TYPE_NAME);
 		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_name_function_file_line(
 			$crate::c_str!($name),
-			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(FUNCTION) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(&FUNCTION) },
 			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
 			line!(),
-		);
+		)
+		.with_module_path(module_path!());
 		&LOC
 	}};
 
 	($color:expr $(,)?) => {{
 		struct S;
-		static FUNCTION: &[u8] = &$crate::zone::get_function_name_from_local_type::<S, 1>();
+		const TYPE_NAME: &str = $crate::zone::type_name_of::<S>();
+		static FUNCTION: [u8; TYPE_NAME.len() - 2] = $crate::zone::get_function_name_from_local_type(TYPE_NAME);
 		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_function_file_line_color(
-			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(FUNCTION) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(&FUNCTION) },
 			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
 			line!(),
 			$color,
-		);
+		)
+		.with_module_path(module_path!());
 		&LOC
 	}};
 
 	($name:literal, $color:expr $(,)?) => {{
 		struct S;
-		static FUNCTION: &[u8] = &$crate::zone::get_function_name_from_local_type::<S, 1>();
+		const TYPE_NAME: &str = $crate::zone::type_name_of::<S>();
+		static FUNCTION: [u8; TYPE_NAME.len() - 2] = $crate::zone::get_function_name_from_local_type(TYPE_NAME);
 		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_name_function_file_line_color(
 			$crate::c_str!($name),
-			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(FUNCTION) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(&FUNCTION) },
 			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
 			line!(),
 			$color,
-		);
+		)
+		.with_module_path(module_path!());
 		&LOC
 	}};
 }
 
 /// Get a zone location.
+///
+/// Without the `unstable` feature, the function name of the location is the path of the enclosing module.
 #[cfg(all(feature = "enable", not(feature = "unstable")))]
 #[macro_export]
 macro_rules! get_location {
 	() => {{
-		struct S;
-		static LOC: $crate::once_cell::sync::Lazy<$crate::zone::ZoneLocation> =
-			$crate::once_cell::sync::Lazy::new(|| {
-				let name = ::std::any::type_name::<S>();
-				let name = name[0..name.len() - 3].as_bytes().to_owned();
-				$crate::zone::ZoneLocation::from_function_file_line(
-					unsafe { ::std::ffi::CString::from_vec_unchecked(name.into()) },
-					unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
-					line!(),
-				)
-			});
-
-		&*LOC
+		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_function_file_line(
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(module_path!(), "\0").as_bytes()) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
+			line!(),
+		)
+		.with_module_path(module_path!());
+		&LOC
 	}};
 
 	($name:literal $(,)?) => {{
-		struct S;
-		static LOC: $crate::once_cell::sync::Lazy<$crate::zone::ZoneLocation> =
-			$crate::once_cell::sync::Lazy::new(|| {
-				let name = ::std::any::type_name::<S>();
-				let name = name[0..name.len() - 3].as_bytes().to_owned();
-				$crate::zone::ZoneLocation::from_name_function_file_line(
-					$crate::c_str!($name),
-					unsafe { ::std::ffi::CString::from_vec_unchecked(name.into()) },
-					unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
-					line!(),
-				)
-			});
-
-		&*LOC
+		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_name_function_file_line(
+			$crate::c_str!($name),
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(module_path!(), "\0").as_bytes()) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
+			line!(),
+		)
+		.with_module_path(module_path!());
+		&LOC
 	}};
 
 	($color:expr $(,)?) => {{
-		struct S;
-		static LOC: $crate::once_cell::sync::Lazy<$crate::zone::ZoneLocation> =
-			$crate::once_cell::sync::Lazy::new(|| {
-				let name = ::std::any::type_name::<S>();
-				let name = name[0..name.len() - 3].as_bytes().to_owned();
-				$crate::zone::ZoneLocation::from_function_file_line_color(
-					unsafe { ::std::ffi::CString::from_vec_unchecked(name.into()) },
-					unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
-					line!(),
-					$color,
-				)
-			});
-
-		&*LOC
+		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_function_file_line_color(
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(module_path!(), "\0").as_bytes()) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
+			line!(),
+			$color,
+		)
+		.with_module_path(module_path!());
+		&LOC
 	}};
 
 	($name:literal, $color:expr $(,)?) => {{
-		struct S;
-		static FN_NAME: $crate::once_cell::sync::Lazy<$crate::zone::ZoneLocation> =
-			$crate::once_cell::sync::Lazy::new(|| {
-				let name = ::std::any::type_name::<S>();
-				let name = name[0..name.len() - 3].as_bytes().to_owned();
-				$crate::zone::ZoneLocation::from_name_function_file_line_color(
-					$crate::c_str!($name),
-					unsafe { ::std::ffi::CString::from_vec_unchecked(name.into()) },
-					unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
-					line!(),
-					$color,
-				)
-			});
-
-		&*LOC
+		static LOC: $crate::zone::ZoneLocation = $crate::zone::ZoneLocation::from_name_function_file_line_color(
+			$crate::c_str!($name),
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(module_path!(), "\0").as_bytes()) },
+			unsafe { ::std::ffi::CStr::from_bytes_with_nul_unchecked(concat!(file!(), "\0").as_bytes()) },
+			line!(),
+			$color,
+		)
+		.with_module_path(module_path!());
+		&LOC
 	}};
 }
 
@@ -403,15 +2273,15 @@ macro_rules! get_location {
 		&$crate::zone::ZoneLocation { loc: () }
 	}};
 
-	($name:literal) => {{
+	($name:literal $(,)?) => {{
 		&$crate::zone::ZoneLocation { loc: () }
 	}};
 
-	($color:expr) => {{
+	($color:expr $(,)?) => {{
 		&$crate::zone::ZoneLocation { loc: () }
 	}};
 
 	($name:literal, $color:expr $(,)?) => {{
-		$crate::zone::ZoneLocation { loc: () }
+		&$crate::zone::ZoneLocation { loc: () }
 	}};
 }
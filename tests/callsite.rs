@@ -0,0 +1,51 @@
+//! Callsite filtering and counters. Kept in its own test binary, because both are global.
+#![cfg(feature = "enable")]
+
+use tracy_full::{c_str, callsite, zone::ZoneLocation};
+
+static RENDER: ZoneLocation = ZoneLocation::from_function_file_line(c_str!("f"), c_str!("file.rs"), 1)
+	.with_module_path("my_crate::render::upload");
+static PHYSICS: ZoneLocation =
+	ZoneLocation::from_name_function_file_line(c_str!("physics"), c_str!("f"), c_str!("file.rs"), 1)
+		.with_module_path("my_crate::render");
+static OTHER: ZoneLocation =
+	ZoneLocation::from_function_file_line(c_str!("f"), c_str!("file.rs"), 1).with_module_path("my_crate_other");
+
+#[test]
+fn filter() {
+	assert!(RENDER.is_enabled());
+
+	callsite::set_filter("my_crate::render=off, physics=on, my_crate=on, invalid=maybe");
+	assert!(!RENDER.is_enabled());
+	assert!(PHYSICS.is_enabled());
+	assert!(OTHER.is_enabled());
+
+	callsite::set_filter("off,my_crate::render::upload=on");
+	assert!(RENDER.is_enabled());
+	assert!(!PHYSICS.is_enabled());
+	assert!(!OTHER.is_enabled());
+
+	OTHER.set_enabled(true);
+	assert!(OTHER.is_enabled());
+
+	let mut registered = 0;
+	callsite::for_each_callsite(|_| registered += 1);
+	assert_eq!(registered, 3);
+
+	callsite::set_filter("");
+	assert!(RENDER.is_enabled() && PHYSICS.is_enabled() && OTHER.is_enabled());
+}
+
+#[test]
+fn call_counter() {
+	use tracy_full::{frame::frame, zone::CallCounter};
+
+	static COUNTER: CallCounter = CallCounter::new(c_str!("counter count"));
+	for _ in 0..3 {
+		COUNTER.increment();
+	}
+	assert_eq!(COUNTER.count(), 3);
+
+	frame();
+	assert_eq!(COUNTER.count(), 0);
+}
@@ -0,0 +1,64 @@
+//! The runtime instrumentation switch. Kept in its own test binary, because it is global.
+
+use tracy_full::{
+	alloc::GlobalAllocator,
+	c_str,
+	frame,
+	get_location,
+	instrumentation_enabled,
+	plotter,
+	set_instrumentation_enabled,
+	zone,
+	zone::{CallCounter, Zone, ZoneBuilder},
+};
+
+#[global_allocator]
+static ALLOC: GlobalAllocator = GlobalAllocator::new();
+
+#[test]
+fn switch() {
+	static COUNTER: CallCounter = CallCounter::new(c_str!("switch count"));
+
+	assert_eq!(instrumentation_enabled(), cfg!(feature = "enable"));
+
+	set_instrumentation_enabled(false);
+	assert!(!instrumentation_enabled());
+	// Freed after instrumentation is switched back on, which must not be reported to Tracy.
+	let allocated_off = Box::new(0u64);
+	{
+		zone!(let zone, "off");
+		assert!(!zone.is_active());
+		assert!(!Zone::here("off").is_active());
+		assert!(!ZoneBuilder::new(get_location!("off")).begin().is_active());
+		zone!("deferred", min = 1ns);
+		frame!(discontinuous "off");
+		plotter!("off").value(1.0);
+		COUNTER.increment();
+		assert_eq!(COUNTER.count(), 0);
+		#[cfg(feature = "futures")]
+		poll_future();
+		frame!();
+	}
+
+	set_instrumentation_enabled(true);
+	drop(allocated_off);
+	let _ = vec![0u8; 1024];
+	zone!(let zone, "on");
+	assert_eq!(zone.is_active(), cfg!(feature = "enable"));
+	frame!(discontinuous "on");
+	COUNTER.increment();
+	assert_eq!(COUNTER.count(), cfg!(feature = "enable") as u64);
+}
+
+#[cfg(feature = "futures")]
+fn poll_future() {
+	use std::{
+		future::Future,
+		pin::pin,
+		task::{Context, Poll, Waker},
+	};
+
+	let mut future = pin!(tracy_full::trace_future!("off", async { 1 }));
+	let poll = future.as_mut().poll(&mut Context::from_waker(Waker::noop()));
+	assert_eq!(poll, Poll::Ready(1));
+}
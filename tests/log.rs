@@ -0,0 +1,41 @@
+//! The `log` backend. Kept in its own test binary, because the logger is global.
+#![cfg(feature = "log")]
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{LevelFilter, Log, Metadata, Record};
+use tracy_full::log::TracyLogger;
+
+static LOGGED: AtomicUsize = AtomicUsize::new(0);
+
+struct Counter;
+
+impl Log for Counter {
+	fn enabled(&self, metadata: &Metadata) -> bool { metadata.level() <= LevelFilter::Warn }
+
+	fn log(&self, _: &Record) { LOGGED.fetch_add(1, Ordering::Relaxed); }
+
+	fn flush(&self) {}
+}
+
+#[test]
+fn tee() {
+	let logger = TracyLogger::new()
+		.with_level(LevelFilter::Info)
+		.with_error_callstack(100)
+		.tee(Counter);
+	let info = Metadata::builder().level(log::Level::Info).build();
+	let debug = Metadata::builder().level(log::Level::Debug).build();
+	assert_eq!(logger.enabled(&info), cfg!(feature = "enable"));
+	assert!(!logger.enabled(&debug));
+
+	log::set_boxed_logger(Box::new(logger)).unwrap();
+	log::set_max_level(LevelFilter::Trace);
+	log::error!("error {}", 1);
+	log::warn!(target: "my_crate::io", "warn");
+	log::info!("info");
+	log::debug!("debug");
+	log::trace!("trace");
+	log::logger().flush();
+	assert_eq!(LOGGED.load(Ordering::Relaxed), 2);
+}
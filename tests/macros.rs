@@ -0,0 +1,493 @@
+//! Every form of the public macros must compile and run in every feature combination.
+
+use tracy_full::{
+	c_str,
+	color::Color,
+	frame,
+	get_location,
+	plotter,
+	zone,
+	zone::{Zone, ZoneLocation},
+	zone_sample,
+};
+
+#[test]
+fn get_location() {
+	let _: &'static ZoneLocation = get_location!();
+	let _: &'static ZoneLocation = get_location!("name");
+	let _: &'static ZoneLocation = get_location!("name",);
+	let _: &'static ZoneLocation = get_location!(Color::RED);
+	let _: &'static ZoneLocation = get_location!(Color::RED,);
+	let _: &'static ZoneLocation = get_location!("name", Color::RED);
+	let _: &'static ZoneLocation = get_location!("name", Color::RED,);
+}
+
+#[test]
+fn zone_location() {
+	let loc: &'static ZoneLocation = get_location!("location");
+	loc.set_enabled(true);
+	assert!(loc.is_enabled());
+	assert_eq!(loc.name(), cfg!(feature = "enable").then_some("location"));
+	assert_eq!(loc.line() != 0, cfg!(feature = "enable"));
+	let _ = (loc.module_path(), loc.function(), loc.file());
+
+	tracy_full::callsite::for_each_callsite(|loc| {
+		let _: (Option<&str>, &str, bool) = (loc.name(), loc.file(), loc.is_enabled());
+	});
+}
+
+#[test]
+fn zone() {
+	let enabled = true;
+
+	zone!();
+	zone!("name");
+	zone!("name",);
+	zone!(Color::RED);
+	zone!(Color::RED,);
+	zone!("name", enabled);
+	zone!("name", false,);
+	zone!(Color::RED, enabled);
+	zone!(Color::RED, false,);
+	zone!("name", Color::RED, enabled);
+	zone!("name", Color::RED, false,);
+}
+
+#[test]
+#[rustfmt::skip]
+fn zone_dynamic() {
+	let name = String::from("dynamic");
+
+	zone!(dynamic &name);
+	zone!(dynamic &name,);
+	zone!(dynamic &name, true);
+	zone!(dynamic "name", false,);
+	zone!(dynamic &name, Color::RED, true);
+	zone!(dynamic &name, Color::RED, false,);
+
+	for i in 0..4 {
+		zone!(dynamic &format!("dynamic {}", i % 2));
+	}
+
+	// Names with a NUL are cut there, rather than panicking.
+	zone!(dynamic "nul\0name");
+	tracy_full::zone::scope("nul\0scope", || {});
+}
+
+#[test]
+fn zone_let() {
+	let name = String::from("dynamic");
+
+	zone!(let zone);
+	let _: &Zone = &zone;
+	zone!(let zone, "name");
+	zone!(let zone, Color::RED);
+	zone!(let zone, "name", true);
+	zone!(let zone, Color::RED, true);
+	zone!(let zone, "name", Color::RED, true,);
+	zone!(let zone, dynamic &name, Color::RED, true);
+
+	zone.text("text");
+	zone.name("renamed");
+	zone.color(Color::GREEN);
+	zone.value(42);
+	let _: bool = zone.is_active();
+}
+
+#[test]
+#[rustfmt::skip]
+fn zone_sample() {
+	let name = String::from("dynamic");
+
+	zone_sample!(8);
+	zone_sample!(8,);
+	zone_sample!("name", 8, true);
+	zone_sample!("name", 8, false,);
+	zone_sample!(Color::RED, 8, true);
+	zone_sample!(Color::RED, 8, false,);
+	zone_sample!("name", Color::RED, 8, true);
+	zone_sample!("name", Color::RED, 100, false,);
+	zone_sample!(dynamic &name, 8, true);
+	zone_sample!(dynamic &name, Color::RED, 8, true,);
+
+	zone_sample!(let zone, 8);
+	zone_sample!(let zone, "name", 8, true);
+	zone_sample!(let zone, Color::RED, 8, true);
+	zone_sample!(let zone, "name", Color::RED, 8, true);
+	zone_sample!(let zone, dynamic &name, 8, true);
+	zone.value(42);
+}
+
+#[test]
+fn frame() {
+	frame!();
+	frame!("secondary");
+	frame!("secondary",);
+	{
+		frame!(discontinuous "discontinuous");
+	}
+	{
+		frame!(discontinuous "discontinuous",);
+	}
+}
+
+#[test]
+fn plotter() {
+	let plotter = plotter!("plot");
+	plotter.value(1.0);
+}
+
+#[test]
+fn c_str() {
+	assert_eq!(c_str!("str").to_bytes(), b"str");
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn trace_future() { drop(tracy_full::trace_future!("future", async {})); }
+
+#[test]
+fn zone_builder() {
+	use tracy_full::zone::ZoneBuilder;
+
+	let name = String::from("dynamic");
+	let _zone = ZoneBuilder::new(get_location!()).begin();
+	let _zone = ZoneBuilder::new(get_location!("name")).callstack(8).begin();
+	let _zone = ZoneBuilder::new(get_location!()).name(&name).color(Color::RED).begin();
+	let _zone = ZoneBuilder::new(get_location!()).active(false).callstack(8).begin();
+	let _zone = ZoneBuilder::here(&name).color(Color::BLUE).callstack(100).begin();
+	let zone = Zone::here("here");
+	zone.text("text");
+}
+
+#[test]
+fn zone_scope() {
+	use tracy_full::zone::{self, ZoneIterator};
+
+	assert_eq!(zone::scope("scope", || 42), 42);
+	assert_eq!((0..10).zoned("zoned").sum::<u32>(), 45);
+	assert_eq!((0..10).zone_each("zone_each").count(), 10);
+
+	let mut partial = (0..10).zoned("partial");
+	assert_eq!(partial.next(), Some(0));
+	drop(partial);
+
+	// The source location of each name is cached, and names the zone on the stack.
+	for name in ["first", "second", "first"] {
+		let stack = zone::scope(name, zone::current_stack);
+		assert_eq!(stack.last().copied(), cfg!(feature = "enable").then_some(name));
+	}
+}
+
+#[test]
+fn zone_each_end() {
+	use tracy_full::zone::{current_stack, ZoneIterator};
+
+	// Records whether a zone was open around each call to `next`.
+	struct Probe<'a>(std::ops::Range<u32>, &'a mut Vec<bool>);
+
+	impl Iterator for Probe<'_> {
+		type Item = u32;
+
+		fn next(&mut self) -> Option<u32> {
+			self.1.push(!current_stack().is_empty());
+			self.0.next()
+		}
+
+		fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+	}
+
+	let mut zoned = Vec::new();
+	assert_eq!(Probe(0..2, &mut zoned).zone_each("each").count(), 2);
+	let enabled = cfg!(feature = "enable");
+	assert_eq!(zoned, [enabled, enabled, false]);
+}
+
+#[test]
+fn zone_text() {
+	use tracy_full::zone_text;
+
+	zone!(let zone, "text");
+	zone_text!(zone, "literal");
+	zone_text!(zone, "formatted {} {:?}", 1, "two");
+	zone_text!(&zone, "{}", "x".repeat(Zone::MAX_TEXT_LEN + 10));
+
+	fn formatted() -> &'static str { panic!("inactive zones must not format their text") }
+	zone!(let inactive, "inactive", false);
+	zone_text!(inactive, "{}", formatted());
+}
+
+#[test]
+fn zone_fields() {
+	use tracy_full::zone_fields;
+
+	let path = String::from("assets/texture.png");
+	let size = 1024u32;
+
+	zone!(path = %path);
+	zone!("fields", path = %path, size = size);
+	zone!("fields", true, kind = ?Some("texture"), value = size,);
+	zone!("fields", Color::RED, true, path = %path, value = size);
+	zone!(let zone, "fields", size = size);
+
+	zone_fields!(zone, path = %path, kind = ?["a", "b"], value = size);
+	zone_fields!(zone, value = 1);
+	zone_fields!(&zone, nested = size + 1);
+}
+
+#[test]
+fn zone_level() {
+	use tracy_full::zone::Level;
+
+	zone!(level = error);
+	zone!(level = warn, "name");
+	zone!(level = info, Color::RED, true);
+	zone!(level = debug, "name", path = "path");
+	zone_sample!(level = trace, "name", 8, true);
+	zone!(let zone, level = trace, dynamic "name");
+	zone.text("text");
+
+	if !Level::TRACE.is_enabled() {
+		assert!(!zone.is_active());
+	}
+	assert!(Level::ERROR <= Level::TRACE);
+}
+
+#[test]
+fn zone_sampled() {
+	use tracy_full::zone::Sampler;
+
+	for _ in 0..10 {
+		zone!(every = 3);
+		zone!(every = 3, "name", true);
+		zone!(limit = 2 per frame, "name");
+		zone!(limit = 2 per second, Color::RED, true);
+		zone!(let zone, every = 2, dynamic "name");
+		zone.value(1);
+	}
+
+	let sampler = Sampler::per_frame(2);
+	let sampled = (0..10).filter(|_| sampler.sample()).count();
+	assert!(sampled == 0 || sampled == 2);
+}
+
+#[test]
+fn zone_min() {
+	use tracy_full::zone::duration_nanos;
+
+	zone!(min = 1ns);
+	zone!("min", min = 100us);
+	zone!("min", true, min = 1_000ns,);
+	zone!("min", Color::RED, true, min = 0s);
+	zone!(let outer, "outer", min = 1ns);
+	for _ in 0..4 {
+		zone!("inner", min = 1ms);
+		zone!("leaf", min = 0ns);
+	}
+	let _: bool = outer.is_active();
+
+	// Modifiers compose with deferred zones.
+	zone!(level = debug, "a", min = 1ms);
+	zone!(every = 2, "b", min = 1ns);
+	zone!(limit = 5 per frame, "c", min = 100us);
+	zone!(limit = 5 per second, "c", min = 100us);
+	zone!(count, "e", min = 1ns);
+	zone!(level = error, every = 2, "f", true, min = 1ns);
+
+	assert_eq!(duration_nanos("100us"), 100_000);
+	assert_eq!(duration_nanos("2ms"), 2_000_000);
+	assert_eq!(duration_nanos("1_000ns"), 1_000);
+	assert_eq!(duration_nanos("3s"), 3_000_000_000);
+}
+
+#[test]
+fn zone_perf() {
+	use tracy_full::{
+		perf::{self, Counter, Reading},
+		zone_text,
+	};
+
+	zone!(perf);
+	zone!(perf, "perf");
+	zone!(perf, "perf", Color::RED, true);
+	zone!(let zone, perf, "perf", value = 1);
+	zone_text!(zone, "text");
+	let _: bool = zone.is_active();
+
+	let start = Reading::now();
+	let sum = (0..1000).sum::<u64>();
+	let delta = Reading::now().since(&start);
+	let available = perf::available();
+	for counter in Counter::ALL {
+		assert_eq!(
+			delta.get(counter).is_some(),
+			available.contains(&counter),
+			"{} {}",
+			counter.name(),
+			sum
+		);
+	}
+	if cfg!(all(feature = "perf-counters", feature = "enable", target_os = "linux")) {
+		// The software counters work without extra privileges.
+		let start = Reading::now();
+		let mut pages = vec![0u8; 64 << 20];
+		for i in (0..pages.len()).step_by(4096) {
+			pages[i] = 1;
+		}
+		let delta = Reading::now().since(&start);
+		assert!(delta.get(Counter::TaskClock).unwrap() > 0);
+		assert!(delta.get(Counter::PageFaults).unwrap() > 0, "{}", pages[4096]);
+	} else {
+		assert!(available.is_empty());
+	}
+
+	perf::set_counters(&[Counter::TaskClock, Counter::PageFaults]);
+	assert!(perf::available()
+		.iter()
+		.all(|c| matches!(c, Counter::TaskClock | Counter::PageFaults)));
+	perf::set_counters(&Counter::ALL);
+}
+
+#[test]
+fn zone_budget() {
+	zone!(budget = 1ms);
+	zone!("budget", budget = 2ms);
+	zone!("budget", true, budget = 500us,);
+	zone!("budget", Color::RED, true, budget = 1s);
+	zone!(let zone, "budget", budget = 16ms);
+	zone.text("text");
+
+	use std::time::Duration;
+
+	// Budgets and the callsite API compile to no-ops without `enable`.
+	let enabled = cfg!(feature = "enable");
+	let loc = get_location!("overrun");
+	for _ in 0..2 {
+		let _zone = tracy_full::zone::zone_budget(loc, 0, true);
+		std::thread::sleep(Duration::from_millis(1));
+	}
+	assert_eq!(loc.budget(), enabled.then_some(Duration::ZERO));
+	assert_eq!(loc.overruns(), if enabled { 2 } else { 0 });
+
+	loc.set_budget(Some(Duration::from_secs(1)));
+	loc.set_budget_callstack(8);
+	{
+		let _zone = tracy_full::zone::zone_budget(loc, 0, true);
+	}
+	assert_eq!(loc.overruns(), if enabled { 2 } else { 0 });
+
+	loc.set_budget(None);
+	assert_eq!(loc.budget(), None);
+
+	tracy_full::callsite::for_each_callsite(|loc| {
+		if loc.name() == Some("overrun") {
+			loc.set_budget(Some(Duration::from_millis(4)));
+			loc.set_budget_callstack(16);
+		}
+	});
+
+	// Modifiers compose with budget zones.
+	zone!(level = debug, "a", budget = 2ms);
+	zone!(every = 10, "b", budget = 2ms);
+	zone!(limit = 5 per frame, "c", budget = 2ms);
+	zone!(limit = 5 per second, "c", budget = 2ms);
+	zone!(perf, "d", budget = 2ms);
+	zone!(count, "e", budget = 2ms);
+	zone!(let zone, level = error, every = 2, perf, "f", true, budget = 2ms);
+	zone.text("text");
+}
+
+#[test]
+#[rustfmt::skip]
+fn zone_stack() {
+	use tracy_full::zone::{self, ZoneBuilder};
+
+	zone::install_stack_panic_hook();
+	assert!(zone::current_stack().is_empty());
+	{
+		zone!("frame");
+		zone!("render");
+		let name = String::from("dynamic");
+		zone!(dynamic &name);
+		zone!("inactive", false);
+		let _builder = ZoneBuilder::new(get_location!("builder")).name(&name).begin();
+		zone!("deferred", min = 1s);
+
+		if cfg!(feature = "enable") {
+			assert_eq!(
+				zone::current_stack(),
+				["frame", "render", "dynamic", "builder", "deferred"]
+			);
+		}
+	}
+	assert!(zone::current_stack().is_empty());
+}
+
+#[test]
+fn zone_count() {
+	for _ in 0..3 {
+		zone!(count, "counted");
+		zone!(count, "counted", Color::RED, true);
+		zone!(count, "counted", size = 1);
+		zone!(let zone, count, "counted", min = 1ms);
+		let _: bool = zone.is_active();
+	}
+}
+
+#[test]
+fn marker() {
+	use tracy_full::marker;
+
+	let bytes = 1024;
+	marker!("marker");
+	marker!("marker",);
+	marker!("marker", Color::GREEN);
+	marker!("marker", "literal");
+	marker!("marker", "{} bytes", bytes);
+	marker!("marker", "{} bytes {}", bytes, "received",);
+	marker!("marker", Color::BLUE, "{} bytes", bytes);
+	marker!("marker", Color::BLUE, "{bytes} bytes");
+}
+
+#[test]
+fn message() {
+	use tracy_full::{message, message::is_plain};
+
+	let path = "level.bin";
+	message!("message");
+	message!("message",);
+	message!("{{escaped}}");
+	message!("{}", path);
+	message!("{path}");
+	message!("{} {}", path, 1,);
+	message!(Color::RED, "message");
+	message!(Color::RED, "{}", path);
+	message!(callstack = 8, "message");
+	message!(callstack = 100, "{}", path);
+	message!(callstack = 8, Color::RED, "message");
+	message!(callstack = 8, Color::RED, "{path}",);
+	tracy_full::message::message(path, None, 0);
+
+	// Messages longer than Tracy accepts are truncated, on a character boundary.
+	message!("{}", "x".repeat(70_000));
+	tracy_full::message::message(&"é".repeat(40_000), Some(Color::RED), 0);
+
+	assert!(is_plain("message"));
+	assert!(!is_plain("{path}"));
+	assert!(!is_plain("{{escaped}}"));
+}
+
+#[test]
+fn app_info() {
+	tracy_full::set_app_info("app info");
+	tracy_full::set_app_info(&"x".repeat(70_000));
+	tracy_full::app_info!();
+
+	#[cfg(feature = "build-info")]
+	{
+		assert!(tracy_full::BUILD_INFO.contains("rustc"));
+		assert!(tracy_full::BUILD_INFO.contains("tracy_full features: "));
+		assert!(tracy_full::BUILD_INFO.contains("build-info"));
+		frame!();
+	}
+}
@@ -0,0 +1,38 @@
+//! The panic hook. Kept in its own test binary, because the hook is global.
+
+use std::panic;
+
+use tracy_full::{zone, zone::current_stack};
+
+#[test]
+fn hook() {
+	tracy_full::zone::install_stack_panic_hook();
+	tracy_full::install_panic_hook();
+
+	let result = panic::catch_unwind(|| {
+		zone!("outer");
+		zone!("inner", min = 1ns);
+		zone!("innermost");
+		panic!("boom");
+	});
+	assert!(result.is_err());
+	assert!(current_stack().is_empty());
+
+	zone!(let zone, "after");
+	assert_eq!(current_stack().len(), zone.is_active() as usize);
+
+	// A caught panic leaves the zones outside `catch_unwind` open.
+	let result = panic::catch_unwind(|| {
+		zone!("inside");
+		panic!("caught");
+	});
+	assert!(result.is_err());
+	assert_eq!(current_stack().len(), zone.is_active() as usize);
+	zone.text("still open");
+
+	// Payloads longer than a message are truncated, rather than aborting inside Tracy.
+	let result = panic::catch_unwind(|| panic!("{}", "x".repeat(70_000)));
+	assert!(result.is_err());
+	let result = panic::catch_unwind(|| assert_eq!(vec![0u8; 20_000], vec![1u8; 20_000]));
+	assert!(result.is_err());
+}
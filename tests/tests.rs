@@ -1,5 +1,37 @@
 use tracy_full as tracy;
 
+#[cfg(feature = "instrument")]
+#[tracy::instrument(level = debug)]
+fn instrumented() {}
+
+#[cfg(feature = "instrument")]
+#[tracy::instrument(name = "instrumented", color = tracy::color::Color::RED, callstack = 8, enabled = x > 2, args(x))]
+fn instrumented_with_options(x: u32) -> u32 { x + 1 }
+
+#[cfg(feature = "instrument")]
+struct Instrumented;
+
+#[cfg(feature = "instrument")]
+impl Instrumented {
+	#[tracy::instrument(args(self))]
+	fn method(&self) -> Result<(), std::fmt::Error> {
+		instrumented();
+		Ok(())
+	}
+}
+
+#[cfg(all(feature = "instrument", feature = "futures"))]
+#[tracy::instrument(enabled = x > 0)]
+async fn instrumented_async(x: u32) -> Result<u32, std::num::TryFromIntError> {
+	let y: u8 = x.try_into()?;
+	Ok(y as u32)
+}
+
+#[cfg(feature = "instrument")]
+impl std::fmt::Debug for Instrumented {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str("Instrumented") }
+}
+
 fn main() {
 	for i in 0..10000 {
 		tracy::frame!("secondary");
@@ -10,6 +42,22 @@ fn main() {
 			tracy::frame!(discontinuous "discontinuous");
 
 			tracy::zone!("hi", true);
+
+			tracy::zone!(let zone, "annotated");
+			zone.text("text");
+			zone.value(i as u64);
+
+			let name = format!("dynamic {}", i % 4);
+			#[rustfmt::skip]
+			tracy::zone!(dynamic &name);
+
+			#[cfg(feature = "instrument")]
+			{
+				instrumented_with_options(i as u32);
+				Instrumented.method().unwrap();
+				#[cfg(feature = "futures")]
+				drop(instrumented_async(i as u32));
+			}
 		}
 	}
 }
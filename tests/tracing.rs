@@ -0,0 +1,20 @@
+//! The `tracing` layer.
+#![cfg(feature = "tracing")]
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracy_full::tracing::{set_error_callstack, TracyLayer};
+
+#[test]
+fn events() {
+	set_error_callstack(100);
+	let subscriber = tracing_subscriber::registry().with(TracyLayer);
+	tracing::subscriber::with_default(subscriber, || {
+		let span = tracing::info_span!("span", id = 1);
+		let _entered = span.enter();
+		tracing::error!(path = "level.bin", "failed to load");
+		tracing::warn!("warn");
+		tracing::info!(target: "my_crate::io", bytes = 1024);
+		tracing::debug!("debug {}", 1);
+		tracing::trace!("trace");
+	});
+}
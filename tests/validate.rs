@@ -0,0 +1,101 @@
+//! Runtime validation of zones, frames, and fibers.
+#![cfg(all(feature = "enable", feature = "validate"))]
+
+use tracy_full::{frame, zone, zone::Zone};
+
+#[test]
+fn nested() {
+	zone!("outer");
+	for _ in 0..3 {
+		zone!("inner");
+		zone!(let deferred, "deferred", min = 1s);
+		let here = Zone::here("here");
+		drop(here);
+		drop(deferred);
+	}
+	frame!(discontinuous "frame");
+	frame!(discontinuous "other");
+}
+
+#[test]
+#[should_panic(expected = "`outer` at tests/validate.rs")]
+fn forgotten() {
+	zone!(let outer, "outer");
+	zone!(let inner, "inner");
+	std::mem::forget(inner);
+	drop(outer);
+}
+
+#[test]
+#[should_panic(expected = "must end in reverse order")]
+fn swapped() {
+	let mut outer = zone::zone(tracy_full::get_location!("outer"), true);
+	let mut inner = zone::zone(tracy_full::get_location!("inner"), true);
+	std::mem::swap(&mut outer, &mut inner);
+}
+
+#[test]
+#[should_panic(expected = "must end in reverse order")]
+fn zipped_iterators() {
+	use tracy_full::zone::ZoneIterator;
+
+	// The zone of `a` ends when it is exhausted, while the zone of `b` is still open.
+	let a = (0..2).zoned("a");
+	let b = (0..3).zoned("b");
+	for _ in a.zip(b) {}
+}
+
+#[test]
+#[should_panic(expected = "discontinuous frame `frame`")]
+fn interleaved_frames() {
+	frame!(discontinuous "frame");
+	frame!(discontinuous "frame");
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn fibers() {
+	use std::{
+		future::Future,
+		pin::pin,
+		task::{Context, Poll, Waker},
+	};
+
+	use tracy_full::trace_future;
+
+	let future = trace_future!("outer", async {
+		zone!("in fiber");
+		trace_future!("inner", async {
+			zone!("in inner fiber");
+		})
+		.await;
+	});
+	let mut context = Context::from_waker(Waker::noop());
+	assert_eq!(pin!(future).poll(&mut context), Poll::Ready(()));
+}
+
+#[cfg(feature = "futures")]
+#[test]
+fn panicking_fiber() {
+	use std::{
+		future::Future,
+		panic::catch_unwind,
+		pin::pin,
+		task::{Context, Poll, Waker},
+	};
+
+	use tracy_full::trace_future;
+
+	// Only ends in the fiber it began in if the panicking future left its fiber.
+	zone!("outer");
+	let result = catch_unwind(|| {
+		let future = trace_future!("panics", async {
+			zone!("in fiber");
+			panic!("panic in fiber");
+		});
+		let mut context = Context::from_waker(Waker::noop());
+		pin!(future).poll(&mut context) == Poll::Ready(())
+	});
+	assert!(result.is_err());
+	zone!("after");
+}